@@ -0,0 +1,82 @@
+use std::fmt::Write;
+use std::path::Path;
+
+#[path = "src/version_string.rs"]
+mod version_string;
+
+const DOCS_PATH: &str = "../docs";
+const SUMMARIES_PATH: &str = "../docs/summaries";
+
+fn main() {
+    generate_versions();
+
+    println!("cargo::rerun-if-changed=build.rs");
+}
+
+/// Finds all the version directories (`docs/vX.Y`) and generates the
+/// `LATEST_VERSION` and `ALL_VERSIONS` constants.
+fn generate_versions() {
+    // this is recursive, so it also covers adding or removing navigation files
+    println!("cargo::rerun-if-changed={DOCS_PATH}");
+
+    let mut versions: Vec<(semver::Version, String)> = std::fs::read_dir(DOCS_PATH)
+        .expect("failed to read the docs directory")
+        .map(|entry| entry.expect("failed to read the docs directory entry"))
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| {
+            let name = entry.file_name().into_string().ok()?;
+            if !name.starts_with('v') {
+                return None;
+            }
+            let version = version_string::canonicalize_version_string(&name);
+            let version = semver::Version::parse(&version).ok()?;
+            Some((version, name))
+        })
+        .collect();
+    versions.sort_by(|(a, _), (b, _)| b.cmp(a));
+
+    assert!(
+        !versions.is_empty(),
+        "no version directories (e.g. `docs/v0.1`) found in `{DOCS_PATH}`"
+    );
+    for (_, name) in &versions {
+        check_navigation_exists(name);
+    }
+
+    let mut output = String::new();
+    writeln!(
+        output,
+        "pub const LATEST_VERSION: &str = {:?};",
+        versions[0].1
+    )
+    .unwrap();
+    writeln!(output, "pub const ALL_VERSIONS: &[&str] = &[").unwrap();
+    writeln!(output, "    MASTER_VERSION,").unwrap();
+    for (_, name) in &versions {
+        writeln!(output, "    {name:?},").unwrap();
+    }
+    writeln!(output, "];").unwrap();
+
+    let out_dir = std::env::var("OUT_DIR").expect("Cargo should set OUT_DIR");
+    std::fs::write(Path::new(&out_dir).join("versions.rs"), output)
+        .expect("failed to write the version list");
+}
+
+/// Makes sure the version has a navigation file that `guide_manifest!` can
+/// use, so that a missing one is reported early, with a helpful message.
+fn check_navigation_exists(version: &str) {
+    let candidates = [
+        format!("{DOCS_PATH}/{version}/docs/SUMMARY.md"),
+        format!("{SUMMARIES_PATH}/{version}.md"),
+    ];
+
+    assert!(
+        candidates
+            .iter()
+            .any(|candidate| Path::new(candidate).is_file()),
+        "version `{version}` does not have a navigation file; expected one of: {} \
+        (if `{DOCS_PATH}/{version}` is a submodule, make sure it is checked out with \
+        `git submodule update --init`)",
+        candidates.join(", ")
+    );
+}
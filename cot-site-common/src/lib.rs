@@ -1,16 +1,13 @@
+mod escape;
 pub mod md_pages;
 mod utils;
+mod version_string;
+pub use escape::escape_html;
 pub use utils::{Version, VersionError};
 
 pub const MASTER_VERSION: &str = "master";
-pub const LATEST_VERSION: &str = "v0.7";
-pub const ALL_VERSIONS: &[&str] = &[
-    MASTER_VERSION,
-    LATEST_VERSION,
-    "v0.6",
-    "v0.5",
-    "v0.4",
-    "v0.3",
-    "v0.2",
-    "v0.1",
-];
+
+// Generated by the build script from the version directories in `docs/`:
+// `LATEST_VERSION` (the newest released version) and `ALL_VERSIONS` (`master`
+// followed by all the versions, newest first).
+include!(concat!(env!("OUT_DIR"), "/versions.rs"));
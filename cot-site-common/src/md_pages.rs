@@ -4,15 +4,55 @@ use std::sync::Mutex;
 use comrak::adapters::{HeadingAdapter, HeadingMeta};
 use comrak::nodes::Sourcepos;
 use serde::Deserialize;
+use thiserror::Error;
+
+use crate::Version;
+
+const FRONT_MATTER_DELIMITER: &str = "---";
 
 #[derive(Debug, Clone)]
 pub struct MdPage {
     pub link: String,
     pub title: String,
+    /// The description from the front matter or, if there isn't one, the
+    /// beginning of the first paragraph of the page.
+    pub description: Option<String>,
+    pub keywords: Vec<String>,
+    pub draft: bool,
+    pub aliases: Vec<String>,
+    pub since: Option<Version>,
+    pub toc_depth: Option<u8>,
+    pub weight: Option<i32>,
     pub content_html: String,
     pub sections: Vec<Section>,
 }
 
+impl MdPage {
+    /// Creates a page with the given link, title and rendered content, and
+    /// the rest of the metadata left empty, as if the front matter only
+    /// contained the title. The pages of the guide are usually created from
+    /// the Markdown files by the `md_page!` macro instead.
+    pub fn new(
+        link: impl Into<String>,
+        title: impl Into<String>,
+        content_html: impl Into<String>,
+    ) -> Self {
+        Self {
+            link: link.into(),
+            title: title.into(),
+            description: None,
+            keywords: Vec::new(),
+            draft: false,
+            aliases: Vec::new(),
+            since: None,
+            toc_depth: None,
+            weight: None,
+            content_html: content_html.into(),
+            sections: Vec::new(),
+        }
+    }
+}
+
 impl From<&MdPage> for MdPageLink {
     fn from(value: &MdPage) -> Self {
         Self {
@@ -28,9 +68,133 @@ pub struct MdPageLink {
     pub title: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+/// The metadata of a Markdown page, stored as YAML at the beginning of the
+/// file, between two `---` lines.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
 pub struct FrontMatter {
     pub title: String,
+    /// A short summary of the page, used in search results and meta tags.
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub keywords: Vec<String>,
+    /// Whether the page is still work in progress; the drafts are not
+    /// served, searchable or listed in the sitemap.
+    #[serde(default)]
+    pub draft: bool,
+    /// Other links (in the same version) that the page used to be available
+    /// under.
+    #[serde(default)]
+    pub aliases: Vec<String>,
+    /// The version of Cot that introduced the feature described by the page.
+    #[serde(default)]
+    pub since: Option<Version>,
+    /// The deepest heading level shown in the table of contents.
+    #[serde(default)]
+    pub toc_depth: Option<u8>,
+    /// The weight of the page used for sorting; lighter pages go first.
+    #[serde(default)]
+    pub weight: Option<i32>,
+}
+
+impl FrontMatter {
+    /// Parses and validates the front matter extracted from a Markdown
+    /// document.
+    pub fn parse(block: &FrontMatterBlock<'_>) -> Result<Self, FrontMatterError> {
+        let front_matter: Self = serde_yml::from_str(block.yaml).map_err(|error| {
+            let (line, column) = error
+                .location()
+                .map_or((1, 1), |location| (location.line(), location.column()));
+            FrontMatterError::Invalid {
+                line: block.yaml_line + line - 1,
+                column,
+                message: error.to_string(),
+            }
+        })?;
+
+        let invalid = |message: &str| FrontMatterError::Invalid {
+            line: block.yaml_line,
+            column: 1,
+            message: message.to_string(),
+        };
+        if front_matter.title.trim().is_empty() {
+            return Err(invalid("`title` must not be empty"));
+        }
+        if front_matter
+            .toc_depth
+            .is_some_and(|depth| !(1..=6).contains(&depth))
+        {
+            return Err(invalid("`toc_depth` must be between 1 and 6"));
+        }
+
+        Ok(front_matter)
+    }
+}
+
+/// The front matter block split off the beginning of a Markdown document.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FrontMatterBlock<'a> {
+    /// The YAML between the delimiters.
+    pub yaml: &'a str,
+    /// The line (1-based) of the document the YAML starts at.
+    pub yaml_line: usize,
+    /// The Markdown content following the front matter.
+    pub body: &'a str,
+    /// The line (1-based) of the document the body starts at.
+    pub body_line: usize,
+}
+
+/// Errors that can occur while extracting and parsing front matter.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+pub enum FrontMatterError {
+    /// The document doesn't start with a `---` line.
+    #[error(
+        "1:1: missing front matter (a YAML block between `---` lines at the start of the file)"
+    )]
+    Missing,
+    /// The closing `---` line was not found.
+    #[error("1:1: front matter is not closed with a `---` line")]
+    Unterminated,
+    /// The front matter is not valid YAML or doesn't match the schema.
+    #[error("{line}:{column}: invalid front matter: {message}")]
+    Invalid {
+        line: usize,
+        column: usize,
+        message: String,
+    },
+}
+
+/// Splits the front matter off the beginning of a Markdown document.
+///
+/// The front matter has to start on the first line of the document and be
+/// delimited by lines consisting of just `---`, so that `---` lines later on
+/// in the document (e.g. horizontal rules) are not mistaken for it.
+pub fn extract_front_matter(content: &str) -> Result<FrontMatterBlock<'_>, FrontMatterError> {
+    let content = content.strip_prefix('\u{feff}').unwrap_or(content);
+    let mut lines = content.split_inclusive('\n');
+
+    let opening = lines.next().ok_or(FrontMatterError::Missing)?;
+    if opening.trim_end() != FRONT_MATTER_DELIMITER {
+        return Err(FrontMatterError::Missing);
+    }
+
+    let yaml_start = opening.len();
+    let mut offset = yaml_start;
+    for (index, line) in lines.enumerate() {
+        if line.trim_end() == FRONT_MATTER_DELIMITER {
+            return Ok(FrontMatterBlock {
+                yaml: &content[yaml_start..offset],
+                yaml_line: 2,
+                body: &content[offset + line.len()..],
+                // the opening delimiter, the YAML lines and the closing delimiter
+                body_line: index + 3,
+            });
+        }
+        offset += line.len();
+    }
+
+    Err(FrontMatterError::Unterminated)
 }
 
 #[derive(Debug, Clone)]
@@ -85,3 +249,100 @@ impl HeadingAdapter for MdPageHeadingAdapter {
         write!(output, "</h{}>", heading.level)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(content: &str) -> Result<FrontMatter, FrontMatterError> {
+        FrontMatter::parse(&extract_front_matter(content)?)
+    }
+
+    #[test]
+    fn test_extract_front_matter() {
+        let block = extract_front_matter("---\ntitle: Intro\n---\n\n# Intro\n\n---\n").unwrap();
+
+        assert_eq!(block.yaml, "title: Intro\n");
+        assert_eq!(block.yaml_line, 2);
+        assert_eq!(block.body, "\n# Intro\n\n---\n");
+        assert_eq!(block.body_line, 4);
+    }
+
+    #[test]
+    fn test_extract_front_matter_crlf() {
+        let block = extract_front_matter("---\r\ntitle: Intro\r\n---\r\nbody").unwrap();
+
+        assert_eq!(block.yaml, "title: Intro\r\n");
+        assert_eq!(block.body, "body");
+    }
+
+    #[test]
+    fn test_extract_front_matter_missing() {
+        assert_eq!(
+            extract_front_matter("# Title\n\n---\n\ntext\n---\n"),
+            Err(FrontMatterError::Missing)
+        );
+        assert_eq!(extract_front_matter(""), Err(FrontMatterError::Missing));
+        assert_eq!(
+            extract_front_matter("---\ntitle: Intro\n"),
+            Err(FrontMatterError::Unterminated)
+        );
+    }
+
+    #[test]
+    fn test_parse_front_matter() {
+        let front_matter = parse(
+            "---\n\
+            title: Databases\n\
+            description: Working with the ORM\n\
+            keywords: [orm, database]\n\
+            draft: true\n\
+            aliases: [db]\n\
+            since: v0.2\n\
+            toc_depth: 3\n\
+            weight: -1\n\
+            ---\n",
+        )
+        .unwrap();
+
+        assert_eq!(front_matter.title, "Databases");
+        assert_eq!(
+            front_matter.description.as_deref(),
+            Some("Working with the ORM")
+        );
+        assert_eq!(front_matter.keywords, vec!["orm", "database"]);
+        assert!(front_matter.draft);
+        assert_eq!(front_matter.aliases, vec!["db"]);
+        assert_eq!(front_matter.since, Some(Version::new(0, 2, 0)));
+        assert_eq!(front_matter.toc_depth, Some(3));
+        assert_eq!(front_matter.weight, Some(-1));
+    }
+
+    #[test]
+    fn test_parse_front_matter_defaults() {
+        let front_matter = parse("---\ntitle: Intro\n---\n").unwrap();
+
+        assert_eq!(front_matter.description, None);
+        assert!(front_matter.keywords.is_empty());
+        assert!(!front_matter.draft);
+        assert_eq!(front_matter.since, None);
+    }
+
+    #[test]
+    fn test_parse_front_matter_unknown_field() {
+        let error = parse("---\ntitle: Intro\ntitel: Typo\n---\n").unwrap_err();
+
+        let FrontMatterError::Invalid { message, .. } = error else {
+            panic!("unexpected error: {error:?}");
+        };
+        assert!(message.contains("titel"));
+    }
+
+    #[test]
+    fn test_parse_front_matter_invalid_values() {
+        assert!(parse("---\ntitle: ''\n---\n").is_err());
+        assert!(parse("---\ntitle: Intro\ntoc_depth: 7\n---\n").is_err());
+        assert!(parse("---\ntitle: Intro\nsince: not a version\n---\n").is_err());
+        assert!(parse("---\ndescription: No title\n---\n").is_err());
+    }
+}
@@ -2,8 +2,10 @@ use std::fmt::Display;
 use std::str::FromStr;
 
 use semver::Version as SemverVersion;
+use serde::{Deserialize, Deserializer};
 use thiserror::Error;
 
+use crate::version_string::canonicalize_version_string;
 use crate::{LATEST_VERSION, MASTER_VERSION};
 
 /// Errors related to version parsing and handling.
@@ -93,13 +95,10 @@ impl FromStr for Version {
     }
 }
 
-fn canonicalize_version_string(s: &str) -> String {
-    let s = s.trim_start_matches('v');
-    let parts: Vec<&str> = s.split('.').collect();
-    match parts.len() {
-        1 => format!("{}.0.0", parts[0]),
-        2 => format!("{}.{}.0", parts[0], parts[1]),
-        _ => s.to_string(),
+impl<'de> Deserialize<'de> for Version {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s = String::deserialize(deserializer)?;
+        Self::from_str(&s).map_err(serde::de::Error::custom)
     }
 }
 
@@ -0,0 +1,14 @@
+// This module is also included in the build script, so it must not depend on
+// anything else in the crate.
+
+/// Turns a version string such as `v0.5` into a full semver version string
+/// (`0.5.0`).
+pub(crate) fn canonicalize_version_string(s: &str) -> String {
+    let s = s.trim_start_matches('v');
+    let parts: Vec<&str> = s.split('.').collect();
+    match parts.len() {
+        1 => format!("{}.0.0", parts[0]),
+        2 => format!("{}.{}.0", parts[0], parts[1]),
+        _ => s.to_string(),
+    }
+}
@@ -1,41 +1,159 @@
 #![cfg_attr(cot_use_nightly, feature(proc_macro_tracked_path))]
 
+use std::path::PathBuf;
+
+use cot_site_common::{ALL_VERSIONS, MASTER_VERSION};
 use proc_macro::TokenStream;
 
-use crate::md_pages::{CodeSampleInput, ExternalMdPageInput, MdPageInput};
+use crate::md_pages::{
+    CodeSampleInput, ExternalMdPageInput, GuideManifestInput, GuideSource, MdPageError, MdPageInput,
+};
 
 mod md_pages;
 
 #[proc_macro]
 pub fn code_sample(input: TokenStream) -> TokenStream {
-    let input = proc_macro2::TokenStream::from(input);
+    code_sample_impl(input.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
 
-    let CodeSampleInput { lang, code } = syn::parse2(input).unwrap();
+fn code_sample_impl(input: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+    let CodeSampleInput { lang, code } = syn::parse2(input)?;
 
-    md_pages::quote_code_sample(&lang, &code).into()
+    md_pages::quote_code_sample(&lang.value(), &code.value())
+        .map_err(|error| syn::Error::new(lang.span(), error))
 }
 
 #[proc_macro]
 pub fn md_page(input: TokenStream) -> TokenStream {
-    let input = proc_macro2::TokenStream::from(input);
+    md_page_impl(input.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
 
-    let MdPageInput { prefix, link } = syn::parse2(input).unwrap();
+fn md_page_impl(input: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+    let MdPageInput { prefix, link } = syn::parse2(input)?;
 
-    let path_prefix = if prefix.is_empty() {
-        "docs/".to_string()
+    let prefix_value = prefix.value();
+    let (path_prefix, include_dir) = if prefix_value.is_empty() {
+        ("docs".to_string(), "docs".to_string())
     } else {
-        format!("docs/{prefix}/docs")
+        (
+            format!("docs/{prefix_value}/docs"),
+            format!("docs/{prefix_value}"),
+        )
     };
-    let md_page = md_pages::parse_md_page(&path_prefix, &link, &prefix);
-    md_pages::quote_md_page(&md_page).into()
+    let md_page = md_pages::parse_md_page(&path_prefix, &link.value(), &prefix_value, &include_dir)
+        .map_err(|error| {
+            // point to the version for version errors, and to the page otherwise
+            let span = match error {
+                MdPageError::InvalidVersion { .. } => prefix.span(),
+                _ => link.span(),
+            };
+            syn::Error::new(span, error)
+        })?;
+    Ok(md_pages::quote_md_page(&md_page))
 }
 
 #[proc_macro]
 pub fn external_md_page(input: TokenStream) -> TokenStream {
+    external_md_page_impl(input.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn external_md_page_impl(input: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+    let ExternalMdPageInput { link } = syn::parse2(input)?;
+
+    let md_page = md_pages::parse_md_page("..", &link.value(), "master", "..")
+        .map_err(|error| syn::Error::new(link.span(), error))?;
+    Ok(md_pages::quote_md_page(&md_page))
+}
+
+/// Generates the guide categories of a version from its navigation file.
+///
+/// The navigation file is read from `docs/{version}/docs/SUMMARY.md`, falling
+/// back to `docs/summaries/{version}.md` for the versions whose docs don't
+/// ship one.
+#[proc_macro]
+pub fn guide_manifest(input: TokenStream) -> TokenStream {
+    guide_manifest_impl(input.into())
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn guide_manifest_impl(input: proc_macro2::TokenStream) -> syn::Result<proc_macro2::TokenStream> {
+    let GuideManifestInput { version } = syn::parse2(input)?;
+
+    quote_version_guide_manifest(&version.value())
+        .map_err(|error| syn::Error::new(version.span(), error))
+}
+
+/// Generates a list of `(version, categories)` tuples for all the released
+/// versions of the guide, as discovered in the `docs` directory.
+#[proc_macro]
+pub fn all_guide_manifests(input: TokenStream) -> TokenStream {
     let input = proc_macro2::TokenStream::from(input);
+    if !input.is_empty() {
+        return syn::Error::new_spanned(input, "expected no arguments")
+            .into_compile_error()
+            .into();
+    }
+
+    all_guide_manifests_impl()
+        .map_err(|error| syn::Error::new(proc_macro2::Span::call_site(), error))
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
+}
+
+fn all_guide_manifests_impl() -> Result<proc_macro2::TokenStream, MdPageError> {
+    let versions = ALL_VERSIONS
+        .iter()
+        .filter(|&&version| version != MASTER_VERSION)
+        .map(|&version| {
+            let categories = quote_version_guide_manifest(version)?;
+            Ok(quote::quote! { (#version, #categories) })
+        })
+        .collect::<Result<Vec<_>, MdPageError>>()?;
+
+    Ok(quote::quote! { vec![#(#versions),*] })
+}
 
-    let ExternalMdPageInput { link } = syn::parse2(input).unwrap();
+fn quote_version_guide_manifest(version: &str) -> Result<proc_macro2::TokenStream, MdPageError> {
+    let prefix = format!("docs/{version}/docs");
+    let include_dir = format!("docs/{version}");
+    let source = GuideSource {
+        manifest_paths: vec![
+            PathBuf::from(format!("{prefix}/SUMMARY.md")),
+            PathBuf::from(format!("docs/summaries/{version}.md")),
+        ],
+        prefix: &prefix,
+        version,
+        include_dir: &include_dir,
+    };
+    md_pages::quote_guide_manifest(&source, &quote::quote! { crate::GuideItem })
+}
 
-    let md_page = md_pages::parse_md_page("..", &link, "master");
-    md_pages::quote_md_page(&md_page).into()
+/// Generates the guide categories from the `SUMMARY.md` file in the parent
+/// directory of the crate; used for the guide of the `master` version.
+#[proc_macro]
+pub fn external_guide_manifest(input: TokenStream) -> TokenStream {
+    let input = proc_macro2::TokenStream::from(input);
+    if !input.is_empty() {
+        return syn::Error::new_spanned(input, "expected no arguments")
+            .into_compile_error()
+            .into();
+    }
+
+    let source = GuideSource {
+        manifest_paths: vec![PathBuf::from("../SUMMARY.md")],
+        prefix: "..",
+        version: "master",
+        include_dir: "..",
+    };
+    md_pages::quote_guide_manifest(&source, &quote::quote! { ::cot_site::GuideItem })
+        .map_err(|error| syn::Error::new(proc_macro2::Span::call_site(), error))
+        .unwrap_or_else(syn::Error::into_compile_error)
+        .into()
 }
@@ -1,78 +1,124 @@
+mod error;
+mod include;
+mod manifest;
 mod rendering;
 
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Mutex;
 
 use cot_site_common::Version;
-use cot_site_common::md_pages::{FrontMatter, MdPage, MdPageHeadingAdapter, Section};
+use cot_site_common::md_pages::{
+    FrontMatter, FrontMatterError, MdPage, MdPageHeadingAdapter, Section, extract_front_matter,
+};
 use proc_macro2::TokenStream;
 use quote::quote;
 use syn::LitStr;
 use syn::parse::{Parse, ParseStream};
 
+use crate::md_pages::error::ContentError;
+pub(super) use crate::md_pages::error::MdPageError;
+use crate::md_pages::include::IncludedContent;
+use crate::md_pages::manifest::{ManifestItem, parse_manifest};
 use crate::md_pages::rendering::markdown_to_html;
 
 pub(super) struct MdPageInput {
-    pub(super) prefix: String,
-    pub(super) link: String,
+    pub(super) prefix: LitStr,
+    pub(super) link: LitStr,
 }
 
 impl Parse for MdPageInput {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let prefix = input.parse::<LitStr>()?.value();
+        let prefix = input.parse::<LitStr>()?;
         input.parse::<syn::Token![,]>()?;
-        let link = input.parse::<LitStr>()?.value();
+        let link = input.parse::<LitStr>()?;
         Ok(Self { prefix, link })
     }
 }
 
 pub(super) struct ExternalMdPageInput {
-    pub(super) link: String,
+    pub(super) link: LitStr,
 }
 
 impl Parse for ExternalMdPageInput {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let link = input.parse::<LitStr>()?.value();
+        let link = input.parse::<LitStr>()?;
         Ok(Self { link })
     }
 }
 
+pub(super) struct GuideManifestInput {
+    pub(super) version: LitStr,
+}
+
+impl Parse for GuideManifestInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let version = input.parse::<LitStr>()?;
+        Ok(Self { version })
+    }
+}
+
 pub(super) struct CodeSampleInput {
-    pub(super) lang: String,
-    pub(super) code: String,
+    pub(super) lang: LitStr,
+    pub(super) code: LitStr,
 }
 
 impl Parse for CodeSampleInput {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let lang = input.parse::<LitStr>()?.value();
+        let lang = input.parse::<LitStr>()?;
         input.parse::<syn::Token![,]>()?;
-        let code = input.parse::<LitStr>()?.value();
+        let code = input.parse::<LitStr>()?;
         Ok(Self { lang, code })
     }
 }
 
-pub(super) fn quote_code_sample(lang: &str, code: &str) -> TokenStream {
-    let html = rendering::render_code_sample(lang, code);
-    quote! { #html }
+pub(super) fn quote_code_sample(lang: &str, code: &str) -> Result<TokenStream, MdPageError> {
+    let html = rendering::render_code_sample(lang, code)?;
+    Ok(quote! { #html })
 }
 
-fn read_md_page(link: &str) -> String {
+fn manifest_dir_path(path: impl AsRef<Path>) -> PathBuf {
     let manifest_dir = std::env::var("CARGO_MANIFEST_DIR").expect("CARGO_MANIFEST_DIR not set");
-    let path = Path::new(&manifest_dir).join(link).with_extension("md");
+    Path::new(&manifest_dir).join(path)
+}
 
+/// Reads a file that the macro output depends on, making sure the macro is
+/// re-run when the file changes (on nightly).
+fn read_tracked_file(path: &Path) -> std::io::Result<String> {
     #[cfg(cot_use_nightly)]
     {
         let path_str = path.to_str().expect("path is not valid UTF-8");
         proc_macro::tracked::path(path_str);
     }
 
-    std::fs::read_to_string(path).expect("failed to read file")
+    std::fs::read_to_string(path)
+}
+
+fn read_md_page(path: &Path) -> Result<String, MdPageError> {
+    read_tracked_file(&manifest_dir_path(path)).map_err(|source| MdPageError::ReadFile {
+        path: path.to_owned(),
+        source,
+    })
 }
 
 pub(super) fn quote_md_page(md_page: &MdPage) -> TokenStream {
     let link = &md_page.link;
     let title = &md_page.title;
+    let description = quote_option(
+        md_page
+            .description
+            .as_ref()
+            .map(|description| quote! { String::from(#description) }),
+    );
+    let keywords = &md_page.keywords;
+    let draft = md_page.draft;
+    let aliases = &md_page.aliases;
+    let since = quote_option(md_page.since.as_ref().map(|since| {
+        let (major, minor, patch) = (since.major(), since.minor(), since.patch());
+        quote! { cot_site_common::Version::new(#major, #minor, #patch) }
+    }));
+    let toc_depth = quote_option(md_page.toc_depth);
+    let weight = quote_option(md_page.weight);
     let content_html = &md_page.content_html;
     let sections = md_page.sections.iter().map(quote_section);
 
@@ -80,6 +126,13 @@ pub(super) fn quote_md_page(md_page: &MdPage) -> TokenStream {
         cot_site_common::md_pages::MdPage {
             link: String::from(#link),
             title: String::from(#title),
+            description: #description,
+            keywords: vec![#(String::from(#keywords)),*],
+            draft: #draft,
+            aliases: vec![#(String::from(#aliases)),*],
+            since: #since,
+            toc_depth: #toc_depth,
+            weight: #weight,
             content_html: String::from(#content_html),
             sections: vec![#(#sections),*],
         }
@@ -87,6 +140,88 @@ pub(super) fn quote_md_page(md_page: &MdPage) -> TokenStream {
     md_page
 }
 
+fn quote_option<T: quote::ToTokens>(value: Option<T>) -> TokenStream {
+    match value {
+        Some(value) => quote! { Some(#value) },
+        None => quote! { None },
+    }
+}
+
+/// Describes where the guide of a single version is located.
+pub(super) struct GuideSource<'a> {
+    /// The possible locations of the navigation file, in order of preference.
+    pub(super) manifest_paths: Vec<PathBuf>,
+    /// The directory containing the Markdown pages.
+    pub(super) prefix: &'a str,
+    pub(super) version: &'a str,
+    /// The directory that `{{#include}}` paths are relative to.
+    pub(super) include_dir: &'a str,
+}
+
+/// Reads the navigation file of the guide and generates the list of
+/// categories, along with the parsed pages, in the format expected by
+/// `CotSiteApp`.
+///
+/// `guide_item` is the path to the `GuideItem` type.
+pub(super) fn quote_guide_manifest(
+    source: &GuideSource<'_>,
+    guide_item: &TokenStream,
+) -> Result<TokenStream, MdPageError> {
+    let manifest_path = source
+        .manifest_paths
+        .iter()
+        .find(|path| manifest_dir_path(path).is_file())
+        .ok_or_else(|| MdPageError::MissingManifest {
+            version: source.version.to_string(),
+            paths: source.manifest_paths.clone(),
+        })?;
+    let manifest = read_md_page(manifest_path)?;
+    let categories = parse_manifest(&manifest).map_err(|source| MdPageError::Content {
+        path: manifest_path.clone(),
+        source,
+    })?;
+
+    let categories = categories
+        .iter()
+        .map(|category| {
+            let title = &category.title;
+            let items = quote_manifest_items(source, guide_item, &category.items)?;
+            Ok(quote! { (#title, #items) })
+        })
+        .collect::<Result<Vec<_>, MdPageError>>()?;
+
+    Ok(quote! { vec![#(#categories),*] })
+}
+
+fn quote_manifest_items(
+    source: &GuideSource<'_>,
+    guide_item: &TokenStream,
+    items: &[ManifestItem],
+) -> Result<TokenStream, MdPageError> {
+    let items = items
+        .iter()
+        .map(|item| match item {
+            ManifestItem::Page(link) => {
+                let md_page =
+                    parse_md_page(source.prefix, link, source.version, source.include_dir)?;
+                let page = quote_md_page(&md_page);
+                Ok(quote! { #guide_item::Page(#page) })
+            }
+            ManifestItem::SubCategory { title, items } => {
+                let items = quote_manifest_items(source, guide_item, items)?;
+                Ok(quote! {
+                    #guide_item::SubCategory {
+                        title: #title,
+                        items: #items,
+                    }
+                })
+            }
+        })
+        .collect::<Result<Vec<_>, MdPageError>>()?;
+
+    Ok(quote! { vec![#(#items),*] })
+}
+
 fn quote_section(section: &Section) -> TokenStream {
     let level = section.level;
     let title = &section.title;
@@ -104,24 +239,45 @@ fn quote_section(section: &Section) -> TokenStream {
     section
 }
 
-pub(super) fn parse_md_page(prefix: &str, link: &str, version: &str) -> MdPage {
-    let file_link = if prefix.is_empty() {
-        link
+/// Parses a Markdown page located at `{prefix}/{link}.md`.
+///
+/// The paths in `{{#include}}` directives are resolved relative to
+/// `include_dir`.
+pub(super) fn parse_md_page(
+    prefix: &str,
+    link: &str,
+    version: &str,
+    include_dir: &str,
+) -> Result<MdPage, MdPageError> {
+    let version = Version::from_str(version).map_err(|source| MdPageError::InvalidVersion {
+        version: version.to_string(),
+        source,
+    })?;
+
+    let path = PathBuf::from(if prefix.is_empty() {
+        format!("{link}.md")
     } else {
-        &format!("{prefix}/{link}")
-    };
-    let md_page_content = read_md_page(file_link);
+        format!("{prefix}/{link}.md")
+    });
+    let md_page_content = read_md_page(&path)?;
+    let md_page_content = include::resolve_includes(
+        &md_page_content,
+        &manifest_dir_path(include_dir),
+        read_tracked_file,
+    )
+    .map_err(|source| MdPageError::Content {
+        path: path.clone(),
+        source,
+    })?;
 
-    let front_matter = md_page_content
-        .split("---")
-        .nth(1)
-        .expect("front matter not found");
-    let front_matter: FrontMatter =
-        serde_yml::from_str(front_matter).expect("invalid front matter");
+    let front_matter_block = extract_front_matter(&md_page_content.content)
+        .map_err(|error| front_matter_error(&path, &md_page_content, error))?;
+    let front_matter = FrontMatter::parse(&front_matter_block)
+        .map_err(|error| front_matter_error(&path, &md_page_content, error))?;
 
     let mut options = comrak::Options::default();
     options.extension.table = true;
-    options.extension.front_matter_delimiter = Some("---".to_string());
+    options.extension.alerts = true;
     options.parse.smart = true;
     options.render.r#unsafe = true;
 
@@ -130,7 +286,7 @@ pub(super) fn parse_md_page(prefix: &str, link: &str, version: &str) -> MdPage {
         sections: Mutex::new(vec![]),
     };
 
-    let syntax_highlighter = rendering::build_syntax_highlighter();
+    let syntax_highlighter = rendering::build_syntax_highlighter()?;
     let render_plugins = comrak::options::RenderPlugins::builder()
         .codefence_syntax_highlighter(&syntax_highlighter)
         .heading_adapter(&heading_adapter)
@@ -139,16 +295,66 @@ pub(super) fn parse_md_page(prefix: &str, link: &str, version: &str) -> MdPage {
         .render(render_plugins)
         .build();
 
-    let version = Version::from_str(version).expect("invalid version in md page prefix");
-    let md_page_content = markdown_to_html(&md_page_content, &options, &plugins, version);
+    let content_html = markdown_to_html(front_matter_block.body, &options, &plugins, version)
+        .map_err(|error| {
+            let error = ContentError {
+                line: error.line + front_matter_block.body_line - 1,
+                ..error
+            };
+            content_error(&path, &md_page_content, error)
+        })?;
     let sections = heading_adapter.sections.lock().unwrap().clone();
     let root_section = fix_section_children(&sections);
 
-    MdPage {
+    Ok(MdPage {
         link: link.to_string(),
         title: front_matter.title,
-        content_html: md_page_content,
+        description: front_matter
+            .description
+            .or_else(|| rendering::extract_description(front_matter_block.body, &options)),
+        keywords: front_matter.keywords,
+        draft: front_matter.draft,
+        aliases: front_matter.aliases,
+        since: front_matter.since,
+        toc_depth: front_matter.toc_depth,
+        weight: front_matter.weight,
+        content_html,
         sections: root_section.children,
+    })
+}
+
+fn front_matter_error(
+    path: &Path,
+    content: &IncludedContent,
+    error: FrontMatterError,
+) -> MdPageError {
+    let source = match error {
+        FrontMatterError::Invalid {
+            line,
+            column,
+            message,
+        } => FrontMatterError::Invalid {
+            line: content.original_line(line),
+            column,
+            message,
+        },
+        error => error,
+    };
+    MdPageError::FrontMatter {
+        path: path.to_owned(),
+        source,
+    }
+}
+
+/// Creates an error for the given location in the Markdown content with the
+/// includes resolved, pointing to the corresponding line in the original file.
+fn content_error(path: &Path, content: &IncludedContent, error: ContentError) -> MdPageError {
+    MdPageError::Content {
+        path: path.to_owned(),
+        source: ContentError {
+            line: content.original_line(error.line),
+            ..error
+        },
     }
 }
 
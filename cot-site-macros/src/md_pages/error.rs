@@ -0,0 +1,74 @@
+use std::path::PathBuf;
+
+use cot_site_common::VersionError;
+use cot_site_common::md_pages::FrontMatterError;
+use thiserror::Error;
+
+/// Errors that can occur while generating Markdown pages and code samples at
+/// compile time.
+#[derive(Debug, Error)]
+pub(crate) enum MdPageError {
+    /// The Markdown file could not be read.
+    #[error("failed to read `{}`: {source}", .path.display())]
+    ReadFile {
+        path: PathBuf,
+        #[source]
+        source: std::io::Error,
+    },
+    /// None of the possible guide navigation files exist.
+    #[error(
+        "no guide navigation file found for `{version}` (expected one of: {})",
+        display_paths(.paths)
+    )]
+    MissingManifest {
+        version: String,
+        paths: Vec<PathBuf>,
+    },
+    /// The front matter is missing, invalid or doesn't match the schema.
+    #[error("{}:{}", .path.display(), .source)]
+    FrontMatter {
+        path: PathBuf,
+        #[source]
+        source: FrontMatterError,
+    },
+    /// The version the page belongs to could not be parsed.
+    #[error("invalid version `{version}`: {source}")]
+    InvalidVersion {
+        version: String,
+        #[source]
+        source: VersionError,
+    },
+    /// The syntax highlighting definitions could not be loaded.
+    #[error("failed to load the syntax highlighting definitions: {0}")]
+    SyntaxHighlighting(String),
+    /// The Markdown content is invalid at a specific location.
+    #[error("{}:{}", .path.display(), .source)]
+    Content {
+        path: PathBuf,
+        #[source]
+        source: ContentError,
+    },
+    /// The info string of a code sample is invalid.
+    #[error("invalid code block info string `{info}`: {message}")]
+    InvalidCodeBlockInfo { info: String, message: String },
+}
+
+fn display_paths(paths: &[PathBuf]) -> String {
+    paths
+        .iter()
+        .map(|path| format!("`{}`", path.display()))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// An error at a specific location in the Markdown content, such as an invalid
+/// `{{#include}}` directive or code block info string.
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{line}:{column}: {message}")]
+pub(crate) struct ContentError {
+    /// The line (1-based) in the Markdown file.
+    pub(crate) line: usize,
+    /// The column (1-based) in the Markdown file.
+    pub(crate) column: usize,
+    pub(crate) message: String,
+}
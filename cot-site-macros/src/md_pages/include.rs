@@ -0,0 +1,402 @@
+use std::path::{Path, PathBuf};
+
+use crate::md_pages::error::ContentError;
+
+const INCLUDE_DIRECTIVE: &str = "{{#include";
+
+/// Markdown content with all the `{{#include}}` directives resolved.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct IncludedContent {
+    pub(super) content: String,
+    /// For every include: the first and the last line of the included content
+    /// in the resolved Markdown, and the line of the directive in the original
+    /// Markdown.
+    line_map: Vec<(usize, usize, usize)>,
+}
+
+impl IncludedContent {
+    /// Maps a line number in the resolved Markdown back to the line number in
+    /// the original file. Lines that come from an included file are mapped to
+    /// the line of the `{{#include}}` directive.
+    pub(super) fn original_line(&self, line: usize) -> usize {
+        let mut shift = 0;
+        for &(start, end, directive_line) in &self.line_map {
+            if line < start {
+                break;
+            }
+            if line <= end {
+                return directive_line;
+            }
+            shift += end - start;
+        }
+        line - shift
+    }
+}
+
+/// Replaces all the `{{#include path:selector}}` directives in the Markdown
+/// content with the contents of the referenced files.
+///
+/// The syntax is compatible with mdBook. The path is relative to `base_dir`
+/// and the (optional) selector can be one of:
+///
+/// * `name` - the lines between `ANCHOR: name` and `ANCHOR_END: name` comments
+///   (the common indentation is removed),
+/// * `5` - line 5,
+/// * `5:10` - lines 5 to 10 (inclusive),
+/// * `5:` - lines from 5 to the end of the file,
+/// * `:10` - lines from the start of the file to line 10.
+///
+/// Lines containing anchor markers are never included. A directive can be
+/// escaped with a backslash (`\{{#include ...}}`), in which case it's left as
+/// is (without the backslash).
+///
+/// Only the files inside `base_dir` can be included. The `read_file` function
+/// is called with the full, canonical path of every included file.
+pub(super) fn resolve_includes<F>(
+    content: &str,
+    base_dir: &Path,
+    mut read_file: F,
+) -> Result<IncludedContent, ContentError>
+where
+    F: FnMut(&Path) -> std::io::Result<String>,
+{
+    let mut result = String::with_capacity(content.len());
+    let mut line_map = Vec::new();
+    let mut rest = content;
+
+    while let Some(start) = rest.find(INCLUDE_DIRECTIVE) {
+        let after_directive = &rest[start + INCLUDE_DIRECTIVE.len()..];
+        let is_directive = after_directive.starts_with(char::is_whitespace);
+
+        if !is_directive {
+            result.push_str(&rest[..start + INCLUDE_DIRECTIVE.len()]);
+            rest = after_directive;
+            continue;
+        }
+        if rest[..start].ends_with('\\') {
+            result.push_str(&rest[..start - 1]);
+            result.push_str(INCLUDE_DIRECTIVE);
+            rest = after_directive;
+            continue;
+        }
+
+        let offset = content.len() - rest.len() + start;
+        let (line, column) = line_and_column(content, offset);
+        let make_error = |message: String| ContentError {
+            line,
+            column,
+            message,
+        };
+
+        let end = after_directive
+            .find("}}")
+            .ok_or_else(|| make_error("unterminated `{{#include}}` directive".to_string()))?;
+        let spec = after_directive[..end].trim();
+        let included = include_file(spec, base_dir, &mut read_file).map_err(make_error)?;
+
+        result.push_str(&rest[..start]);
+        let included_start = result.matches('\n').count() + 1;
+        let included_end = included_start + included.matches('\n').count();
+        line_map.push((included_start, included_end, line));
+        result.push_str(&included);
+        rest = &after_directive[end + "}}".len()..];
+    }
+
+    result.push_str(rest);
+    Ok(IncludedContent {
+        content: result,
+        line_map,
+    })
+}
+
+fn line_and_column(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    let column = before[line_start..].chars().count() + 1;
+    (line, column)
+}
+
+fn include_file<F>(spec: &str, base_dir: &Path, read_file: &mut F) -> Result<String, String>
+where
+    F: FnMut(&Path) -> std::io::Result<String>,
+{
+    if spec.is_empty() {
+        return Err("no file specified in the `{{#include}}` directive".to_string());
+    }
+
+    let (path, selector) = match spec.split_once(':') {
+        Some((path, selector)) => (path, Some(selector)),
+        None => (spec, None),
+    };
+    let full_path: PathBuf = base_dir.join(path);
+    let to_error = |error| format!("failed to include `{}`: {error}", full_path.display());
+    // resolve `..` and the symlinks, so that nothing outside of the base
+    // directory can be included
+    let full_path = full_path.canonicalize().map_err(to_error)?;
+    if !full_path.starts_with(base_dir.canonicalize().map_err(to_error)?) {
+        return Err(format!(
+            "`{path}` is outside of `{}` and can't be included",
+            base_dir.display()
+        ));
+    }
+    let file_content = read_file(&full_path).map_err(to_error)?;
+    let lines: Vec<&str> = file_content.lines().collect();
+
+    let selected = match selector {
+        None => lines,
+        Some(selector) => match parse_line_range(selector)? {
+            Some((start, end)) => {
+                let end = end.unwrap_or(lines.len());
+                if start > lines.len() || end > lines.len() {
+                    return Err(format!(
+                        "line range `{selector}` is out of bounds for `{path}`, which has {} lines",
+                        lines.len()
+                    ));
+                }
+                lines[start - 1..end].to_vec()
+            }
+            None => anchored_lines(&lines, selector)
+                .map_err(|message| format!("{message} in `{path}`"))?,
+        },
+    };
+
+    let selected: Vec<&str> = selected
+        .into_iter()
+        .filter(|line| anchor_name(line, "ANCHOR:").is_none())
+        .filter(|line| anchor_name(line, "ANCHOR_END:").is_none())
+        .collect();
+    Ok(selected.join("\n"))
+}
+
+/// Parses a line range selector. Returns `Ok(None)` if the selector is not a
+/// line range (i.e. it's an anchor name).
+fn parse_line_range(selector: &str) -> Result<Option<(usize, Option<usize>)>, String> {
+    let is_range = selector.chars().all(|c| c.is_ascii_digit() || c == ':');
+    if !is_range {
+        return Ok(None);
+    }
+
+    let parse_line = |line: &str| {
+        line.parse::<usize>()
+            .ok()
+            .filter(|&line| line > 0)
+            .ok_or_else(|| format!("invalid line range `{selector}`"))
+    };
+
+    let (start, end) = match selector.split_once(':') {
+        None => {
+            let line = parse_line(selector)?;
+            (line, Some(line))
+        }
+        Some((start, end)) => {
+            let start = if start.is_empty() {
+                1
+            } else {
+                parse_line(start)?
+            };
+            let end = if end.is_empty() {
+                None
+            } else {
+                Some(parse_line(end)?)
+            };
+            (start, end)
+        }
+    };
+
+    if end.is_some_and(|end| end < start) {
+        return Err(format!("invalid line range `{selector}`"));
+    }
+    Ok(Some((start, end)))
+}
+
+/// Returns the lines between `ANCHOR: name` and `ANCHOR_END: name` markers,
+/// with the common indentation removed.
+fn anchored_lines<'a>(lines: &[&'a str], anchor: &str) -> Result<Vec<&'a str>, String> {
+    let start = lines
+        .iter()
+        .position(|line| anchor_name(line, "ANCHOR:") == Some(anchor))
+        .ok_or_else(|| format!("anchor `{anchor}` not found"))?;
+    let length = lines[start + 1..]
+        .iter()
+        .position(|line| anchor_name(line, "ANCHOR_END:") == Some(anchor))
+        .ok_or_else(|| format!("anchor `{anchor}` is never closed with `ANCHOR_END: {anchor}`"))?;
+    let selected = &lines[start + 1..start + 1 + length];
+
+    let indent = selected
+        .iter()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| line.len() - line.trim_start().len())
+        .min()
+        .unwrap_or(0);
+    Ok(selected
+        .iter()
+        .map(|line| line.get(indent..).unwrap_or_default())
+        .collect())
+}
+
+/// Returns the name of the anchor if the line contains an anchor marker
+/// (`ANCHOR:` or `ANCHOR_END:`).
+fn anchor_name<'a>(line: &'a str, marker: &str) -> Option<&'a str> {
+    let index = line.find(marker)?;
+    let name = line[index + marker.len()..].trim_start();
+    let end = name
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-'))
+        .unwrap_or(name.len());
+    let name = &name[..end];
+    (!name.is_empty()).then_some(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::OnceLock;
+
+    use super::*;
+
+    const MAIN_RS: &str = "\
+use cot::project::App;
+// ANCHOR: app
+struct HelloApp;
+
+impl App for HelloApp {
+    // ANCHOR: name
+    fn name(&self) -> &'static str {
+        \"hello\"
+    }
+    // ANCHOR_END: name
+}
+// ANCHOR_END: app
+";
+
+    /// Returns the directory the includes are resolved relative to. It
+    /// contains `examples/main.rs`, and its parent contains `secret.rs`.
+    fn base_dir() -> &'static Path {
+        static BASE_DIR: OnceLock<PathBuf> = OnceLock::new();
+        BASE_DIR.get_or_init(|| {
+            let dir = std::env::temp_dir().join("cot-site-include-tests");
+            std::fs::create_dir_all(dir.join("cot/examples")).unwrap();
+            std::fs::write(dir.join("cot/examples/main.rs"), MAIN_RS).unwrap();
+            std::fs::write(dir.join("secret.rs"), "const SECRET: &str = \"secret\";").unwrap();
+            dir.join("cot")
+        })
+    }
+
+    fn resolve_with_line_map(content: &str) -> Result<IncludedContent, ContentError> {
+        resolve_includes(content, base_dir(), |path| std::fs::read_to_string(path))
+    }
+
+    fn resolve(content: &str) -> Result<String, ContentError> {
+        resolve_with_line_map(content).map(|included| included.content)
+    }
+
+    #[test]
+    fn test_include_whole_file() {
+        let result = resolve("```rust\n{{#include examples/main.rs}}\n```").unwrap();
+        assert_eq!(
+            result,
+            "```rust\nuse cot::project::App;\nstruct HelloApp;\n\nimpl App for HelloApp {\n    fn name(&self) -> &'static str {\n        \"hello\"\n    }\n}\n```"
+        );
+    }
+
+    #[test]
+    fn test_include_anchor() {
+        assert_eq!(
+            resolve("{{#include examples/main.rs:name}}").unwrap(),
+            "fn name(&self) -> &'static str {\n    \"hello\"\n}"
+        );
+        assert_eq!(
+            resolve("{{#include examples/main.rs:app}}").unwrap(),
+            "struct HelloApp;\n\nimpl App for HelloApp {\n    fn name(&self) -> &'static str {\n        \"hello\"\n    }\n}"
+        );
+    }
+
+    #[test]
+    fn test_include_line_ranges() {
+        assert_eq!(
+            resolve("{{#include examples/main.rs:1}}").unwrap(),
+            "use cot::project::App;"
+        );
+        assert_eq!(
+            resolve("{{#include examples/main.rs:3:4}}").unwrap(),
+            "struct HelloApp;\n"
+        );
+        assert_eq!(
+            resolve("{{#include examples/main.rs::3}}").unwrap(),
+            "use cot::project::App;\nstruct HelloApp;"
+        );
+        assert_eq!(resolve("{{#include examples/main.rs:11:}}").unwrap(), "}");
+    }
+
+    #[test]
+    fn test_include_escaped() {
+        assert_eq!(
+            resolve("\\{{#include examples/main.rs}} {{#included}}").unwrap(),
+            "{{#include examples/main.rs}} {{#included}}"
+        );
+    }
+
+    #[test]
+    fn test_include_missing_file() {
+        let error = resolve("Some text\n\n  {{#include examples/missing.rs}}").unwrap_err();
+        assert_eq!(error.line, 3);
+        assert_eq!(error.column, 3);
+        assert!(error.message.contains("/cot/examples/missing.rs"));
+    }
+
+    #[test]
+    fn test_include_outside_base_dir() {
+        let error = resolve("Some text\n{{#include ../secret.rs}}").unwrap_err();
+        assert_eq!(error.line, 2);
+        assert_eq!(error.column, 1);
+        assert_eq!(
+            error.message,
+            format!(
+                "`../secret.rs` is outside of `{}` and can't be included",
+                base_dir().display()
+            )
+        );
+
+        let secret_path = base_dir().join("../secret.rs").canonicalize().unwrap();
+        let error = resolve(&format!("{{{{#include {}}}}}", secret_path.display())).unwrap_err();
+        assert!(error.message.contains("is outside of"));
+    }
+
+    #[test]
+    fn test_include_missing_anchor() {
+        let error = resolve("{{#include examples/main.rs:missing}}").unwrap_err();
+        assert_eq!(
+            error.message,
+            "anchor `missing` not found in `examples/main.rs`"
+        );
+    }
+
+    #[test]
+    fn test_include_invalid_range() {
+        assert!(resolve("{{#include examples/main.rs:5:2}}").is_err());
+        assert!(resolve("{{#include examples/main.rs:0}}").is_err());
+        assert!(resolve("{{#include examples/main.rs:100}}").is_err());
+        assert!(resolve("{{#include examples/main.rs:2:100}}").is_err());
+    }
+
+    #[test]
+    fn test_include_unterminated() {
+        assert!(resolve("{{#include examples/main.rs").is_err());
+        assert!(resolve("{{#include }}").is_err());
+    }
+
+    #[test]
+    fn test_original_line() {
+        let included = resolve_with_line_map(
+            "line 1\n{{#include examples/main.rs:app}}\nline 3\n{{#include examples/main.rs:1}}\nline 5",
+        )
+        .unwrap();
+        assert_eq!(included.content.lines().count(), 11);
+
+        assert_eq!(included.original_line(1), 1);
+        assert_eq!(included.original_line(2), 2);
+        assert_eq!(included.original_line(8), 2);
+        assert_eq!(included.original_line(9), 3);
+        assert_eq!(included.original_line(10), 4);
+        assert_eq!(included.original_line(11), 5);
+    }
+}
@@ -0,0 +1,313 @@
+use crate::md_pages::error::ContentError;
+
+/// A category of the guide, as defined in the navigation file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) struct ManifestCategory {
+    pub(super) title: String,
+    pub(super) items: Vec<ManifestItem>,
+}
+
+/// An entry in a guide category, as defined in the navigation file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(super) enum ManifestItem {
+    /// A link to a page, relative to the docs directory and without the `.md`
+    /// extension.
+    Page(String),
+    SubCategory {
+        title: String,
+        items: Vec<ManifestItem>,
+    },
+}
+
+/// A single list item of the navigation file.
+struct ListItem<'a> {
+    line: usize,
+    indent: usize,
+    text: &'a str,
+    link: Option<String>,
+}
+
+impl ListItem<'_> {
+    fn error(&self, message: String) -> ContentError {
+        ContentError {
+            line: self.line,
+            column: self.indent + 1,
+            message,
+        }
+    }
+}
+
+/// Parses a guide navigation file.
+///
+/// The format is a subset of mdBook's `SUMMARY.md`: every `## Title` heading
+/// starts a new category, and the list below it contains the links to the
+/// pages in that category. A list item without a link starts a subcategory,
+/// which contains the items listed (indented) below it. Subcategories can be
+/// nested:
+///
+/// ```markdown
+/// # Summary
+///
+/// ## Getting started
+///
+/// - [Introduction](introduction.md)
+/// - Database
+///   - [Overview](databases/overview.md)
+///   - Backends
+///     - [PostgreSQL](databases/backends/postgres.md)
+///
+/// ## Upgrading
+///
+/// - [Upgrade guide](upgrade-guide.md)
+/// ```
+///
+/// The link texts are only there to make the file readable; the titles shown
+/// on the website come from the front matter of the pages.
+pub(super) fn parse_manifest(content: &str) -> Result<Vec<ManifestCategory>, ContentError> {
+    // the categories, along with the line of the heading and the list items
+    let mut categories: Vec<(ManifestCategory, usize, Vec<ListItem<'_>>)> = Vec::new();
+
+    for (index, line) in content.lines().enumerate() {
+        let line_number = index + 1;
+        let trimmed = line.trim_start();
+        let indent = line.len() - trimmed.len();
+        let trimmed = trimmed.trim_end();
+        let error = |message: String| ContentError {
+            line: line_number,
+            column: indent + 1,
+            message,
+        };
+
+        if trimmed.is_empty() || trimmed.starts_with("<!--") {
+            continue;
+        }
+
+        if let Some(title) = trimmed.strip_prefix("## ") {
+            let category = ManifestCategory {
+                title: title.trim().to_string(),
+                items: Vec::new(),
+            };
+            categories.push((category, line_number, Vec::new()));
+            continue;
+        }
+        if trimmed.starts_with("# ") && categories.is_empty() {
+            // the title of the whole file
+            continue;
+        }
+
+        let Some(item) = trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "))
+        else {
+            return Err(error(
+                "expected a `## Category` heading or a list item".to_string(),
+            ));
+        };
+        let (_, _, list_items) = categories
+            .last_mut()
+            .ok_or_else(|| error("list item outside of a `## Category` section".to_string()))?;
+        let text = item.trim();
+        list_items.push(ListItem {
+            line: line_number,
+            indent,
+            text,
+            link: parse_link(text).map_err(error)?,
+        });
+    }
+
+    categories
+        .into_iter()
+        .map(|(mut category, line, list_items)| {
+            if list_items.is_empty() {
+                return Err(ContentError {
+                    line,
+                    column: 1,
+                    message: format!("`## {}` does not contain any pages", category.title),
+                });
+            }
+
+            let mut list_items = list_items.into_iter().peekable();
+            category.items = build_items(&mut list_items, 0)?;
+            if let Some(item) = list_items.next() {
+                return Err(item.error("inconsistent indentation".to_string()));
+            }
+            Ok(category)
+        })
+        .collect()
+}
+
+/// Builds the tree of items from the consecutive list items with the given
+/// indentation (along with their children).
+fn build_items<'a, I>(
+    list_items: &mut std::iter::Peekable<I>,
+    indent: usize,
+) -> Result<Vec<ManifestItem>, ContentError>
+where
+    I: Iterator<Item = ListItem<'a>>,
+{
+    let mut items = Vec::new();
+
+    while let Some(item) = list_items.next_if(|item| item.indent == indent) {
+        let child_indent = list_items
+            .peek()
+            .map(|next| next.indent)
+            .filter(|&next_indent| next_indent > indent);
+
+        match (item.link.clone(), child_indent) {
+            (Some(link), None) => items.push(ManifestItem::Page(link)),
+            (Some(_), Some(_)) => {
+                let child = list_items.next().expect("just peeked");
+                return Err(child.error(
+                    "indented list items are only allowed inside a subcategory".to_string(),
+                ));
+            }
+            (None, Some(child_indent)) => items.push(ManifestItem::SubCategory {
+                title: item.text.to_string(),
+                items: build_items(list_items, child_indent)?,
+            }),
+            (None, None) => {
+                return Err(item.error(format!("`{}` does not contain any pages", item.text)));
+            }
+        }
+    }
+
+    if let Some(next) = list_items.peek()
+        && next.indent > indent
+    {
+        let next = list_items.next().expect("just peeked");
+        return Err(next.error("inconsistent indentation".to_string()));
+    }
+
+    Ok(items)
+}
+
+/// Parses a list item in the `[Title](link.md)` format, returning the link
+/// without the extension, or `None` if the item is not a link.
+fn parse_link(item: &str) -> Result<Option<String>, String> {
+    if !item.starts_with('[') {
+        return Ok(None);
+    }
+
+    let target = item
+        .split_once("](")
+        .and_then(|(_text, rest)| rest.strip_suffix(')'))
+        .ok_or_else(|| format!("invalid link `{item}`, expected `[Title](page.md)`"))?;
+    let target = target.trim().trim_start_matches("./");
+    let link = target
+        .strip_suffix(".md")
+        .ok_or_else(|| format!("link target `{target}` is not a Markdown file"))?;
+
+    Ok(Some(link.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUMMARY: &str = "\
+# Summary
+
+## Getting started
+
+- [Introduction](introduction.md)
+- Database
+  - [Overview](databases/overview.md)
+  - Backends
+    - [PostgreSQL](databases/backends/postgres.md)
+  - [Queries](./databases/queries.md)
+- [Testing](testing.md)
+
+## Upgrading
+
+* [Upgrade guide](upgrade-guide.md)
+";
+
+    #[test]
+    fn test_parse_manifest() {
+        let categories = parse_manifest(SUMMARY).unwrap();
+
+        assert_eq!(
+            categories,
+            vec![
+                ManifestCategory {
+                    title: "Getting started".to_string(),
+                    items: vec![
+                        ManifestItem::Page("introduction".to_string()),
+                        ManifestItem::SubCategory {
+                            title: "Database".to_string(),
+                            items: vec![
+                                ManifestItem::Page("databases/overview".to_string()),
+                                ManifestItem::SubCategory {
+                                    title: "Backends".to_string(),
+                                    items: vec![ManifestItem::Page(
+                                        "databases/backends/postgres".to_string()
+                                    )],
+                                },
+                                ManifestItem::Page("databases/queries".to_string()),
+                            ],
+                        },
+                        ManifestItem::Page("testing".to_string()),
+                    ],
+                },
+                ManifestCategory {
+                    title: "Upgrading".to_string(),
+                    items: vec![ManifestItem::Page("upgrade-guide".to_string())],
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_manifest_invalid_link() {
+        let error = parse_manifest("## Guide\n\n- [Intro](intro.html)\n").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 1));
+        assert!(error.message.contains("intro.html"));
+
+        let error = parse_manifest("## Guide\n\n- [Intro](intro.md\n").unwrap_err();
+        assert_eq!(error.line, 3);
+    }
+
+    #[test]
+    fn test_parse_manifest_item_outside_category() {
+        let error = parse_manifest("# Summary\n\n- [Intro](intro.md)\n").unwrap_err();
+        assert_eq!(error.line, 3);
+    }
+
+    #[test]
+    fn test_parse_manifest_unexpected_nesting() {
+        let error =
+            parse_manifest("## Guide\n\n- [Intro](intro.md)\n  - [Sub](sub.md)\n").unwrap_err();
+        assert_eq!((error.line, error.column), (4, 3));
+
+        let error = parse_manifest("## Guide\n\n  - [Intro](intro.md)\n").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 3));
+    }
+
+    #[test]
+    fn test_parse_manifest_inconsistent_indentation() {
+        let error = parse_manifest(
+            "## Guide\n\n- Database\n    - [Overview](overview.md)\n  - [Queries](queries.md)\n",
+        )
+        .unwrap_err();
+        assert_eq!((error.line, error.column), (5, 3));
+    }
+
+    #[test]
+    fn test_parse_manifest_empty() {
+        let error = parse_manifest("## Guide\n\n## Other\n\n- [Intro](intro.md)\n").unwrap_err();
+        assert_eq!(error.line, 1);
+        assert!(error.message.contains("## Guide"));
+
+        let error = parse_manifest("## Guide\n\n- Database\n").unwrap_err();
+        assert_eq!((error.line, error.column), (3, 1));
+
+        let error = parse_manifest("## Guide\n\n- Database\n  - Models\n").unwrap_err();
+        assert_eq!((error.line, error.column), (4, 3));
+    }
+
+    #[test]
+    fn test_parse_manifest_unexpected_line() {
+        let error = parse_manifest("## Guide\n\nSome text\n").unwrap_err();
+        assert_eq!(error.line, 3);
+    }
+}
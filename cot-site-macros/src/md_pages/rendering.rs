@@ -1,13 +1,19 @@
+mod code_block;
+
+use std::collections::HashMap;
 use std::fmt;
 use std::fmt::Write;
 
 use comrak::html::{
     ChildRendering, Context, format_document_with_formatter, format_node_default, render_sourcepos,
 };
-use comrak::nodes::{AstNode, NodeCodeBlock, NodeLink, NodeValue};
+use comrak::nodes::{AlertType, AstNode, NodeAlert, NodeCodeBlock, NodeLink, NodeValue};
 use comrak::options::Plugins;
 use comrak::{Arena, Options, parse_document};
-use cot_site_common::Version;
+use cot_site_common::{Version, escape_html};
+
+use crate::md_pages::error::{ContentError, MdPageError};
+use crate::md_pages::rendering::code_block::CodeBlockInfo;
 
 const COT_RUSTDOC_BASE_URL: &str = "https://docs.rs/cot";
 const COT_RUSTDOC_CRATE_OVERVIEW_URL: &str = "https://docs.rs/crate/cot";
@@ -15,27 +21,35 @@ const COT_RUSTDOC_CRATE_OVERVIEW_URL: &str = "https://docs.rs/crate/cot";
 #[derive(Debug, Clone)]
 struct PageContext {
     version: Version,
+    /// The number of code tab groups rendered so far, used to generate unique
+    /// element IDs.
+    code_tab_groups: usize,
 }
 
-pub(super) fn build_syntax_highlighter() -> comrak::plugins::syntect::SyntectAdapter {
-    comrak::plugins::syntect::SyntectAdapterBuilder::new()
+pub(super) fn build_syntax_highlighter()
+-> Result<comrak::plugins::syntect::SyntectAdapter, MdPageError> {
+    let syntax_set = syntect::dumps::from_uncompressed_data(include_bytes!(
+        "../../../syntax-highlighting/defs.bin"
+    ))
+    .map_err(|error| MdPageError::SyntaxHighlighting(error.to_string()))?;
+
+    Ok(comrak::plugins::syntect::SyntectAdapterBuilder::new()
         .css()
-        .syntax_set(
-            syntect::dumps::from_uncompressed_data(include_bytes!(
-                "../../../syntax-highlighting/defs.bin"
-            ))
-            .expect("failed to load syntax set"),
-        )
-        .build()
+        .syntax_set(syntax_set)
+        .build())
 }
 
-pub(super) fn render_code_sample(lang: &str, code: &str) -> String {
+pub(super) fn render_code_sample(lang: &str, code: &str) -> Result<String, MdPageError> {
+    CodeBlockInfo::parse(lang).map_err(|message| MdPageError::InvalidCodeBlockInfo {
+        info: lang.to_string(),
+        message,
+    })?;
     let md = format!("```{lang}\n{code}\n```");
 
     let mut options = Options::default();
     options.render.r#unsafe = true;
 
-    let syntax_highlighter = build_syntax_highlighter();
+    let syntax_highlighter = build_syntax_highlighter()?;
     let render_plugins = comrak::options::RenderPlugins::builder()
         .codefence_syntax_highlighter(&syntax_highlighter)
         .build();
@@ -52,8 +66,8 @@ pub(super) fn render_code_sample(lang: &str, code: &str) -> String {
         format_node_code_sample,
         (),
     )
-    .unwrap();
-    s
+    .expect("writing to a String should not fail");
+    Ok(s)
 }
 
 fn format_node_code_sample<'a>(
@@ -73,22 +87,30 @@ fn render_code_block_plain<'a>(
     entering: bool,
     cb: &NodeCodeBlock,
 ) -> Result<ChildRendering, fmt::Error> {
-    let mut new_cb = cb.clone();
-    new_cb.literal = remove_hidden_lines(&cb.literal);
+    let (new_cb, _info) = prepare_code_block(cb);
     let node = AstNode::from(NodeValue::CodeBlock(Box::new(new_cb)));
     format_node_default(context, &node, entering)
 }
 
+/// Renders the Markdown to HTML.
+///
+/// Returns an error pointing to the offending location if the Markdown
+/// contains a code block with an invalid info string.
 pub(super) fn markdown_to_html(
     md: &str,
     options: &Options,
     plugins: &Plugins,
     version: Version,
-) -> String {
+) -> Result<String, ContentError> {
     let arena = Arena::new();
     let root = parse_document(&arena, md, options);
+    validate_code_blocks(root)?;
+
     let mut s = String::new();
-    let page_context = PageContext { version };
+    let page_context = PageContext {
+        version,
+        code_tab_groups: 0,
+    };
     format_document_with_formatter(
         root,
         options,
@@ -97,9 +119,102 @@ pub(super) fn markdown_to_html(
         format_node_custom,
         page_context,
     )
-    .unwrap();
+    .expect("writing to a String should not fail");
+
+    Ok(s)
+}
+
+/// The maximum length (in characters) of the descriptions extracted from the
+/// pages, which is about what the search engines show.
+const MAX_DESCRIPTION_LENGTH: usize = 160;
+
+/// Extracts a description of the page from its first paragraph, as plain text
+/// shortened to [`MAX_DESCRIPTION_LENGTH`]; used for the pages that don't have
+/// a description in their front matter.
+///
+/// Only the top-level paragraphs are considered, so that e.g. an alert at the
+/// top of the page doesn't end up in the description.
+pub(super) fn extract_description(md: &str, options: &Options) -> Option<String> {
+    let arena = Arena::new();
+    let root = parse_document(&arena, md, options);
+    let paragraph = root
+        .children()
+        .find(|node| matches!(node.data.borrow().value, NodeValue::Paragraph))?;
+
+    let mut text = String::new();
+    for node in paragraph.descendants() {
+        match node.data.borrow().value {
+            NodeValue::Text(ref literal) => text.push_str(literal),
+            NodeValue::Code(ref code) => text.push_str(&code.literal),
+            NodeValue::SoftBreak | NodeValue::LineBreak => text.push(' '),
+            _ => {}
+        }
+    }
 
-    s
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.is_empty() {
+        return None;
+    }
+    Some(shorten(&text, MAX_DESCRIPTION_LENGTH))
+}
+
+/// Shortens the text to at most `max_length` characters, cutting it at a word
+/// boundary and adding an ellipsis if needed.
+fn shorten(text: &str, max_length: usize) -> String {
+    if text.chars().count() <= max_length {
+        return text.to_string();
+    }
+
+    let mut shortened = String::new();
+    for word in text.split(' ') {
+        // one character is reserved for the ellipsis
+        if shortened.chars().count() + word.chars().count() + 2 > max_length {
+            break;
+        }
+        if !shortened.is_empty() {
+            shortened.push(' ');
+        }
+        shortened.push_str(word);
+    }
+    let shortened = shortened.trim_end_matches(|c: char| c.is_ascii_punctuation());
+    format!("{shortened}…")
+}
+
+/// Checks that the info strings of all the code blocks in the document are
+/// valid, so that they can be rendered without errors, and that the tabs of
+/// every code tab group have different labels, as switching the tabs (and
+/// keeping the groups in sync) is done by their labels.
+fn validate_code_blocks<'a>(root: &'a AstNode<'a>) -> Result<(), ContentError> {
+    for node in root.descendants() {
+        let data = node.data.borrow();
+        if let NodeValue::CodeBlock(ref cb) = data.value {
+            let info = CodeBlockInfo::parse(&cb.info).map_err(|message| ContentError {
+                line: data.sourcepos.start.line,
+                column: data.sourcepos.start.column,
+                message: format!("invalid code block info string `{}`: {message}", cb.info),
+            })?;
+
+            if let Some(group) = &info.group {
+                let mut previous = node.previous_sibling();
+                while let Some(tab) = previous.filter(|&n| is_in_code_tab_group(Some(n), group)) {
+                    let tab_info = code_block_info(tab).expect("tab node is a code block");
+                    if tab_info.tab_label() == info.tab_label() {
+                        return Err(ContentError {
+                            line: data.sourcepos.start.line,
+                            column: data.sourcepos.start.column,
+                            message: format!(
+                                "duplicate tab `{}` in the code tab group `{group}`",
+                                info.tab_label()
+                            ),
+                        });
+                    }
+                    previous = tab.previous_sibling();
+                }
+            }
+        }
+    }
+
+    Ok(())
 }
 
 fn format_node_custom<'a>(
@@ -110,7 +225,11 @@ fn format_node_custom<'a>(
     match node.data.borrow().value {
         NodeValue::Table(_) => render_table_custom(context, node, entering),
         NodeValue::Link(ref ln) => render_link_custom(context, node, entering, ln),
-        NodeValue::CodeBlock(ref cb) => render_code_block_custom(context, node, entering, cb),
+        NodeValue::CodeBlock(ref cb) => match code_tab_group(node) {
+            Some(group) => render_code_tab_custom(context, node, entering, cb, &group),
+            None => render_code_block_custom(context, node, entering, cb),
+        },
+        NodeValue::Alert(ref alert) => render_alert_custom(context, node, entering, alert),
         _ => format_node_default(context, node, entering),
     }
 }
@@ -121,36 +240,349 @@ fn render_code_block_custom<'a>(
     entering: bool,
     cb: &NodeCodeBlock,
 ) -> Result<ChildRendering, fmt::Error> {
+    let (new_cb, info) = prepare_code_block(cb);
+
     if entering {
-        context.write_str("<div class=\"code-block\">")?;
+        context.write_str("<div class=\"code-block")?;
+        if info.title.is_some() {
+            context.write_str(" code-block-with-title")?;
+        }
+        if info.linenos {
+            context.write_str(" code-block-linenos")?;
+        }
+        context.write_str("\">")?;
+
+        let badge = info.badge().map(|(text, modifier)| {
+            format!("<span class=\"code-block-badge code-block-badge-{modifier}\">{text}</span>")
+        });
+        if let Some(title) = &info.title {
+            context.write_str("<div class=\"code-block-title\">")?;
+            context.write_str(&escape_html(title))?;
+            if let Some(badge) = &badge {
+                context.write_str(badge)?;
+            }
+            context.write_str("</div>")?;
+        } else if let Some(badge) = &badge {
+            context.write_str(badge)?;
+        }
         context.write_str("<button type=\"button\" class=\"code-block-copy-btn\" data-copy-code aria-label=\"copy\" title=\"copy\">Copy</button>")?;
     }
 
-    let mut new_cb = cb.clone();
-    new_cb.literal = remove_hidden_lines(&cb.literal);
+    if info.has_line_options() {
+        if entering {
+            render_code_block_lines(context, &new_cb, &info)?;
+        }
+    } else {
+        let node = AstNode::from(NodeValue::CodeBlock(Box::new(new_cb)));
+        format_node_default(context, &node, entering)?;
+    }
 
-    let node = AstNode::from(NodeValue::CodeBlock(Box::new(new_cb)));
+    if !entering {
+        context.write_str("</div>")?;
+    }
+
+    Ok(ChildRendering::HTML)
+}
+
+/// Returns the parsed info string of the node if it's a code block.
+fn code_block_info<'a>(node: &'a AstNode<'a>) -> Option<CodeBlockInfo> {
+    match node.data.borrow().value {
+        NodeValue::CodeBlock(ref cb) => CodeBlockInfo::parse(&cb.info).ok(),
+        _ => None,
+    }
+}
+
+/// Returns the name of the tab group of the node if it's a code block that is
+/// a part of one.
+fn code_tab_group<'a>(node: &'a AstNode<'a>) -> Option<String> {
+    code_block_info(node).and_then(|info| info.group)
+}
 
-    format_node_default(context, &node, entering)?;
+fn is_in_code_tab_group<'a>(node: Option<&'a AstNode<'a>>, group: &str) -> bool {
+    node.and_then(code_tab_group).as_deref() == Some(group)
+}
+
+/// Renders a code block that is a part of a tab group. All the consecutive
+/// code blocks with the same group are rendered as a single tab widget, with
+/// each code block (along with its copy button) in a separate tab panel.
+///
+/// Switching the tabs is handled by `code-tabs.js`, which also keeps all the
+/// widgets with the same group on the page in sync.
+fn render_code_tab_custom<'a>(
+    context: &mut Context<PageContext>,
+    node: &'a AstNode<'a>,
+    entering: bool,
+    cb: &NodeCodeBlock,
+    group: &str,
+) -> Result<ChildRendering, fmt::Error> {
+    let is_first = !is_in_code_tab_group(node.previous_sibling(), group);
+    let is_last = !is_in_code_tab_group(node.next_sibling(), group);
+
+    if entering {
+        if is_first {
+            context.user.code_tab_groups += 1;
+            let group_id = context.user.code_tab_groups;
+
+            context.cr()?;
+            write!(
+                context,
+                "<div class=\"code-tabs\" data-code-tab-group=\"{}\">",
+                escape_html(group)
+            )?;
+            context.write_str("<div class=\"nav nav-tabs code-tabs-nav\" role=\"tablist\">")?;
+            let mut tab_node = Some(node);
+            let mut index = 0;
+            while let Some(current) = tab_node.filter(|&n| is_in_code_tab_group(Some(n), group)) {
+                let info = code_block_info(current).expect("tab node is a code block");
+                let label = escape_html(info.tab_label());
+                let selected = index == 0;
+                write!(
+                    context,
+                    "<button type=\"button\" class=\"nav-link{}\" role=\"tab\" \
+                    id=\"code-tab-{group_id}-{index}\" aria-controls=\"code-tab-panel-{group_id}-{index}\" \
+                    aria-selected=\"{selected}\" tabindex=\"{}\" data-code-tab=\"{label}\">{label}</button>",
+                    if selected { " active" } else { "" },
+                    if selected { "0" } else { "-1" },
+                )?;
+                tab_node = current.next_sibling();
+                index += 1;
+            }
+            context.write_str("</div>")?;
+        }
+
+        let group_id = context.user.code_tab_groups;
+        let mut index = 0;
+        let mut previous = node.previous_sibling();
+        while is_in_code_tab_group(previous, group) {
+            index += 1;
+            previous = previous.and_then(|n| n.previous_sibling());
+        }
+        let info = code_block_info(node).expect("tab node is a code block");
+        write!(
+            context,
+            "<div class=\"code-tab-panel\" role=\"tabpanel\" id=\"code-tab-panel-{group_id}-{index}\" \
+            aria-labelledby=\"code-tab-{group_id}-{index}\" data-code-tab=\"{}\"{}>",
+            escape_html(info.tab_label()),
+            if index == 0 { "" } else { " hidden" },
+        )?;
+    }
+
+    render_code_block_custom(context, node, entering, cb)?;
 
     if !entering {
         context.write_str("</div>")?;
+        if is_last {
+            context.write_str("</div>\n")?;
+        }
     }
 
     Ok(ChildRendering::HTML)
 }
 
-fn remove_hidden_lines(input: &str) -> String {
+/// Renders a code block with every line wrapped in its own `<span>`, so that
+/// lines can be highlighted and numbered (the numbers are added with CSS, so
+/// they are not included when the code is copied).
+fn render_code_block_lines<T>(
+    context: &mut Context<T>,
+    cb: &NodeCodeBlock,
+    info: &CodeBlockInfo,
+) -> fmt::Result {
+    let mut code_attributes = HashMap::new();
+    if !info.lang.is_empty() {
+        code_attributes.insert("class", format!("language-{}", info.lang).into());
+    }
+
+    let mut highlighted = String::new();
+    context.cr()?;
+    match context.plugins.render.codefence_syntax_highlighter {
+        Some(highlighter) => {
+            highlighter.write_pre_tag(context, HashMap::new())?;
+            highlighter.write_code_tag(context, code_attributes)?;
+            highlighter.write_highlighted(
+                &mut highlighted,
+                Some(info.lang.as_str()),
+                &cb.literal,
+            )?;
+        }
+        None => {
+            context.write_str("<pre>")?;
+            if info.lang.is_empty() {
+                context.write_str("<code>")?;
+            } else {
+                write!(
+                    context,
+                    "<code class=\"language-{}\">",
+                    escape_html(&info.lang)
+                )?;
+            }
+            highlighted = escape_html(&cb.literal);
+        }
+    }
+
+    for (index, line) in split_highlighted_lines(&highlighted).iter().enumerate() {
+        let class = if info.is_line_highlighted(index + 1) {
+            "code-line code-line-highlighted"
+        } else {
+            "code-line"
+        };
+        writeln!(context, "<span class=\"{class}\">{line}</span>")?;
+    }
+
+    context.write_str("</code></pre>\n")
+}
+
+/// Splits the highlighted HTML code into lines, making sure every line is a
+/// valid HTML fragment on its own: the `<span>`s that are open at the end of
+/// a line are closed there and reopened at the start of the next line.
+fn split_highlighted_lines(html: &str) -> Vec<String> {
+    let mut lines = Vec::new();
+    let mut open_tags: Vec<&str> = Vec::new();
+    let mut line = String::new();
+    let mut line_has_text = false;
+    let mut rest = html;
+
+    while let Some(c) = rest.chars().next() {
+        if let Some(after) = rest.strip_prefix("</span>") {
+            open_tags.pop();
+            line.push_str("</span>");
+            rest = after;
+        } else if rest.starts_with("<span")
+            && let Some(end) = rest.find('>')
+        {
+            let tag = &rest[..=end];
+            open_tags.push(tag);
+            line.push_str(tag);
+            rest = &rest[end + 1..];
+        } else if c == '\n' {
+            for _ in &open_tags {
+                line.push_str("</span>");
+            }
+            lines.push(std::mem::take(&mut line));
+            line.extend(open_tags.iter().copied());
+            line_has_text = false;
+            rest = &rest[1..];
+        } else {
+            line.push(c);
+            line_has_text = true;
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    if line_has_text {
+        lines.push(line);
+    }
+    lines
+}
+
+/// Parses the info string of the code block and returns a copy of the block
+/// that is ready to be passed to the syntax highlighter: the info string is
+/// replaced with just the language name (so that e.g. `rust,no_run` is still
+/// highlighted as Rust) and the hidden lines are removed.
+fn prepare_code_block(cb: &NodeCodeBlock) -> (NodeCodeBlock, CodeBlockInfo) {
+    let info = CodeBlockInfo::parse(&cb.info)
+        .expect("code block info strings are validated before rendering");
+
+    let mut new_cb = cb.clone();
+    new_cb.info = info.lang.clone();
+    new_cb.literal = remove_hidden_lines(&info, &cb.literal);
+
+    (new_cb, info)
+}
+
+/// Removes the lines hidden from the reader in Rust code blocks, following
+/// the same rules as rustdoc:
+///
+/// * a line consisting of a single `#` is removed,
+/// * a line starting with `# ` (after optional indentation) is removed,
+/// * a line starting with `##` is kept, with the first `#` removed (this is how
+///   a literal `#` at the start of a line can be written).
+///
+/// Code blocks in other languages are returned unchanged, because `#` is
+/// usually the comment character there.
+fn remove_hidden_lines(info: &CodeBlockInfo, input: &str) -> String {
+    if !info.is_rust() {
+        return input.to_string();
+    }
+
     let mut literal = String::new();
     for line in input.lines() {
-        if !line.starts_with("# ") {
+        let trimmed = line.trim();
+        if trimmed.starts_with("##") {
+            literal.push_str(&line.replacen("##", "#", 1));
+        } else if trimmed == "#" || trimmed.starts_with("# ") {
+            continue;
+        } else {
             literal.push_str(line);
-            literal.push('\n');
         }
+        literal.push('\n');
     }
     literal
 }
 
+/// Renders GitHub-style alerts (`> [!NOTE]`, `> [!WARNING]`, etc.) as
+/// Bootstrap alerts with an icon.
+fn render_alert_custom<'a>(
+    context: &mut Context<PageContext>,
+    node: &'a AstNode<'a>,
+    entering: bool,
+    alert: &NodeAlert,
+) -> Result<ChildRendering, fmt::Error> {
+    let (kind, default_title, bootstrap_class, icon) = match alert.alert_type {
+        AlertType::Note => (
+            "note",
+            "Note",
+            "info",
+            include_str!("../../../templates/icons/info_circle.svg"),
+        ),
+        AlertType::Tip => (
+            "tip",
+            "Tip",
+            "success",
+            include_str!("../../../templates/icons/lightbulb.svg"),
+        ),
+        AlertType::Important => (
+            "important",
+            "Important",
+            "primary",
+            include_str!("../../../templates/icons/exclamation_square.svg"),
+        ),
+        AlertType::Warning => (
+            "warning",
+            "Warning",
+            "warning",
+            include_str!("../../../templates/icons/exclamation_triangle.svg"),
+        ),
+        AlertType::Caution => (
+            "caution",
+            "Caution",
+            "danger",
+            include_str!("../../../templates/icons/exclamation_octagon.svg"),
+        ),
+    };
+
+    if entering {
+        context.cr()?;
+        write!(
+            context,
+            "<div class=\"alert alert-{bootstrap_class} callout callout-{kind}\" role=\"note\""
+        )?;
+        render_sourcepos(context, node)?;
+        context.write_str(
+            ">\n<p class=\"callout-title\"><span class=\"callout-icon\" aria-hidden=\"true\">",
+        )?;
+        context.write_str(icon.trim())?;
+        context.write_str("</span>")?;
+        let title = alert.title.as_deref().unwrap_or(default_title);
+        context.write_str(&escape_html(title))?;
+        context.write_str("</p>\n")?;
+    } else {
+        context.cr()?;
+        context.write_str("</div>\n")?;
+    }
+
+    Ok(ChildRendering::HTML)
+}
+
 fn render_table_custom<'a>(
     context: &mut Context<PageContext>,
     node: &'a AstNode<'a>,
@@ -311,6 +743,7 @@ mod tests {
         ($route:expr, $expected:expr) => {
             let user_data = PageContext {
                 version: Version::new(1, 2, 3),
+                code_tab_groups: 0,
             };
             let url = resolve_url($route, &user_data);
             assert_eq!(url, $expected);
@@ -335,17 +768,300 @@ mod tests {
         test_resolve!("cot", "cot");
     }
 
-    #[test]
-    fn test_code_block_filtering() {
-        let md = "```rust\n# hidden\nvisible\n```";
+    fn render_md(md: &str) -> String {
         let mut options = Options::default();
+        options.extension.alerts = true;
         options.render.r#unsafe = true;
         let plugins = Plugins::default();
         let version = Version::new(0, 5, 0);
 
-        let html = markdown_to_html(md, &options, &plugins, version);
+        markdown_to_html(md, &options, &plugins, version).unwrap()
+    }
+
+    fn remove_lines(info: &str, code: &str) -> String {
+        remove_hidden_lines(&CodeBlockInfo::parse(info).unwrap(), code)
+    }
+
+    #[test]
+    fn test_code_block_filtering() {
+        let html = render_md("```rust\n# hidden\nvisible\n```");
 
         assert!(html.contains("visible"));
         assert!(!html.contains("hidden"));
     }
+
+    #[test]
+    fn test_invalid_code_block_info_location() {
+        let options = Options::default();
+        let plugins = Plugins::default();
+        let md = "# Title\n\nSome text\n\n```rust hl_lines=\"5-3\"\nfn main() {}\n```\n";
+
+        let error = markdown_to_html(md, &options, &plugins, Version::new(0, 5, 0)).unwrap_err();
+
+        assert_eq!(error.line, 5);
+        assert_eq!(error.column, 1);
+        assert!(error.message.contains("hl_lines"));
+    }
+
+    #[test]
+    fn test_code_tabs_duplicate_label() {
+        let options = Options::default();
+        let plugins = Plugins::default();
+        let md = "```toml group=\"dep\" tab=\"Cargo.toml\"\nx\n```\n\
+            ```bash group=\"dep\" tab=\"CLI\"\ny\n```\n\
+            ```toml group=\"dep\" tab=\"Cargo.toml\"\nz\n```\n";
+
+        let error = markdown_to_html(md, &options, &plugins, Version::new(0, 5, 0)).unwrap_err();
+
+        assert_eq!(error.line, 7);
+        assert_eq!(error.column, 1);
+        assert!(error.message.contains("duplicate tab `Cargo.toml`"));
+    }
+
+    #[test]
+    fn test_render_code_sample_invalid_info() {
+        let error = render_code_sample(r#"rust hl_lines="x""#, "fn main() {}").unwrap_err();
+
+        assert!(matches!(error, MdPageError::InvalidCodeBlockInfo { .. }));
+    }
+
+    #[test]
+    fn test_remove_hidden_lines_rust() {
+        let code = "# use cot::App;\n#\n    # let x = 1;\nfn main() {}\n";
+        assert_eq!(remove_lines("rust", code), "fn main() {}\n");
+        assert_eq!(remove_lines("rs", code), "fn main() {}\n");
+        assert_eq!(remove_lines("rust,no_run", code), "fn main() {}\n");
+        assert_eq!(remove_lines("compile_fail", code), "fn main() {}\n");
+    }
+
+    #[test]
+    fn test_remove_hidden_lines_rust_escape() {
+        let code = "##[derive(Debug)]\n    ## not hidden\nstruct Foo;\n";
+        assert_eq!(
+            remove_lines("rust", code),
+            "#[derive(Debug)]\n    # not hidden\nstruct Foo;\n"
+        );
+    }
+
+    #[test]
+    fn test_remove_hidden_lines_rust_attributes() {
+        let code = "#[cot::main]\n#![allow(unused)]\n#hidden_not\n";
+        assert_eq!(remove_lines("rust", code), code);
+    }
+
+    #[test]
+    fn test_remove_hidden_lines_toml() {
+        let code = "# SQLite\nurl = \"sqlite://db.sqlite3\"\n#\n## heading\n";
+        assert_eq!(remove_lines("toml", code), code);
+    }
+
+    #[test]
+    fn test_remove_hidden_lines_shell() {
+        let code = "# install the CLI\ncargo install cot-cli\n";
+        assert_eq!(remove_lines("bash", code), code);
+        assert_eq!(remove_lines("sh", code), code);
+        assert_eq!(remove_lines("shell", code), code);
+    }
+
+    #[test]
+    fn test_remove_hidden_lines_html() {
+        let code = "# not a comment, but still text\n<p>Hello</p>\n";
+        assert_eq!(remove_lines("html", code), code);
+    }
+
+    #[test]
+    fn test_remove_hidden_lines_jinja() {
+        let code = "{# comment #}\n# heading\n{{ title }}\n";
+        assert_eq!(remove_lines("html.j2", code), code);
+    }
+
+    #[test]
+    fn test_remove_hidden_lines_diff() {
+        let code = "-# old\n+# new\n # unchanged\n";
+        assert_eq!(remove_lines("diff", code), code);
+    }
+
+    #[test]
+    fn test_remove_hidden_lines_plain_text() {
+        let code = "# plain text\n#\n";
+        assert_eq!(remove_lines("", code), code);
+        assert_eq!(remove_lines("text", code), code);
+    }
+
+    #[test]
+    fn test_code_block_filtering_keeps_comments_without_lang() {
+        let html =
+            render_md("```title=\"setup.sh\"\n# install the CLI\ncargo install cot-cli\n```");
+        assert!(html.contains("# install the CLI"));
+
+        let html =
+            render_md("```group=setup tab=CLI\n# install the CLI\ncargo install cot-cli\n```");
+        assert!(html.contains("# install the CLI"));
+    }
+
+    #[test]
+    fn test_code_block_filtering_keeps_comments_in_other_langs() {
+        let html = render_md("```toml\n# SQLite\nurl = \"sqlite://db.sqlite3\"\n```");
+        assert!(html.contains("# SQLite"));
+
+        let html = render_md("```bash\n# install the CLI\ncargo install cot-cli\n```");
+        assert!(html.contains("# install the CLI"));
+    }
+
+    #[test]
+    fn test_code_block_rustdoc_attributes() {
+        let html = render_md("```rust,no_run\n# hidden\nfn main() {}\n```");
+        assert!(
+            html.contains(
+                "<span class=\"code-block-badge code-block-badge-no-run\">not run</span>"
+            )
+        );
+        assert!(html.contains("language-rust\""));
+        assert!(!html.contains("hidden"));
+
+        let html = render_md("```compile_fail\nlet x: i32 = \"\";\n```");
+        assert!(html.contains("does not compile"));
+        assert!(html.contains("language-rust\""));
+
+        let html = render_md("```rust,ignore\nfn main() {}\n```");
+        assert!(!html.contains("code-block-badge"));
+        assert!(html.contains("language-rust\""));
+    }
+
+    #[test]
+    fn test_split_highlighted_lines() {
+        let html =
+            "<span class=\"source rust\"><span class=\"comment\">/* a\nb */</span>\nfn\n</span>";
+        assert_eq!(
+            split_highlighted_lines(html),
+            vec![
+                "<span class=\"source rust\"><span class=\"comment\">/* a</span></span>",
+                "<span class=\"source rust\"><span class=\"comment\">b */</span></span>",
+                "<span class=\"source rust\">fn</span>",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_split_highlighted_lines_keeps_empty_lines() {
+        assert_eq!(split_highlighted_lines("a\n\nb\n"), vec!["a", "", "b"]);
+        assert_eq!(split_highlighted_lines("a &lt; b"), vec!["a &lt; b"]);
+    }
+
+    #[test]
+    fn test_code_block_title() {
+        let html = render_md("```rust title=\"src/<main>.rs\"\nfn main() {}\n```");
+        assert!(html.contains("<div class=\"code-block code-block-with-title\">"));
+        assert!(html.contains("<div class=\"code-block-title\">src/&lt;main&gt;.rs</div>"));
+        assert!(html.contains("language-rust\""));
+        assert!(!html.contains("code-line"));
+    }
+
+    #[test]
+    fn test_code_block_line_options() {
+        let html = render_md("```rust hl_lines=\"2\" linenos\n# hidden\nfn a() {}\nfn b() {}\n```");
+        assert!(html.contains("<div class=\"code-block code-block-linenos\">"));
+        assert!(html.contains("<code class=\"language-rust\">"));
+        assert!(html.contains("<span class=\"code-line\">fn a() {}</span>\n"));
+        assert!(
+            html.contains("<span class=\"code-line code-line-highlighted\">fn b() {}</span>\n")
+        );
+        assert!(!html.contains("hidden"));
+    }
+
+    #[test]
+    fn test_alerts() {
+        for (marker, class, title) in [
+            ("NOTE", "alert alert-info callout callout-note", "Note"),
+            ("TIP", "alert alert-success callout callout-tip", "Tip"),
+            (
+                "IMPORTANT",
+                "alert alert-primary callout callout-important",
+                "Important",
+            ),
+            (
+                "WARNING",
+                "alert alert-warning callout callout-warning",
+                "Warning",
+            ),
+            (
+                "CAUTION",
+                "alert alert-danger callout callout-caution",
+                "Caution",
+            ),
+        ] {
+            let html = render_md(&format!("> [!{marker}]\n> Some **text**."));
+            assert!(html.contains(&format!("<div class=\"{class}\" role=\"note\">")));
+            assert!(html.contains(&format!("</span>{title}</p>")));
+            assert!(html.contains("<svg"));
+            assert!(html.contains("<p>Some <strong>text</strong>.</p>"));
+            assert!(!html.contains("<blockquote>"));
+        }
+    }
+
+    #[test]
+    fn test_alert_custom_title() {
+        let html = render_md("> [!WARNING] Read <this>\n> Careful.");
+        assert!(html.contains("</span>Read &lt;this&gt;</p>"));
+    }
+
+    #[test]
+    fn test_plain_blockquote() {
+        let html = render_md("> Just a quote.");
+        assert!(html.contains("<blockquote>"));
+        assert!(!html.contains("callout"));
+    }
+
+    #[test]
+    fn test_extract_description() {
+        let mut options = Options::default();
+        options.extension.alerts = true;
+
+        let md = "# Title\n\n> [!NOTE]\n> A note.\n\nCot has an *ORM*, see\n[`Model`](https://docs.rs).\n\nMore.";
+        assert_eq!(
+            extract_description(md, &options).as_deref(),
+            Some("Cot has an ORM, see Model.")
+        );
+        assert_eq!(
+            extract_description("# Title\n\n```\ncode\n```", &options),
+            None
+        );
+    }
+
+    #[test]
+    fn test_extract_description_long() {
+        let md = "word ".repeat(100);
+        let description = extract_description(&md, &Options::default()).unwrap();
+
+        assert!(description.chars().count() <= MAX_DESCRIPTION_LENGTH);
+        assert!(description.ends_with("word…"));
+    }
+
+    #[test]
+    fn test_code_tabs() {
+        let html = render_md(
+            "```toml group=\"dep\" tab=\"Cargo.toml\"\n[dependencies]\ncot = \"0.5\"\n```\n\
+            ```bash group=\"dep\" tab=\"CLI\"\ncargo add cot\n```\n",
+        );
+
+        assert_eq!(html.matches("<div class=\"code-tabs\"").count(), 1);
+        assert!(html.contains("<div class=\"code-tabs\" data-code-tab-group=\"dep\">"));
+        assert!(html.contains("id=\"code-tab-1-0\" aria-controls=\"code-tab-panel-1-0\" aria-selected=\"true\" tabindex=\"0\" data-code-tab=\"Cargo.toml\">Cargo.toml</button>"));
+        assert!(html.contains("id=\"code-tab-1-1\" aria-controls=\"code-tab-panel-1-1\" aria-selected=\"false\" tabindex=\"-1\" data-code-tab=\"CLI\">CLI</button>"));
+        assert!(html.contains("id=\"code-tab-panel-1-0\" aria-labelledby=\"code-tab-1-0\" data-code-tab=\"Cargo.toml\">"));
+        assert!(html.contains("id=\"code-tab-panel-1-1\" aria-labelledby=\"code-tab-1-1\" data-code-tab=\"CLI\" hidden>"));
+        assert_eq!(html.matches("data-copy-code").count(), 2);
+    }
+
+    #[test]
+    fn test_code_tabs_separate_groups() {
+        let html = render_md(
+            "```toml group=\"a\" tab=\"A\"\nx\n```\n\nSome text.\n\n\
+            ```toml group=\"a\" tab=\"A\"\ny\n```\n```toml group=\"b\" tab=\"B\"\nz\n```\n",
+        );
+
+        assert_eq!(html.matches("<div class=\"code-tabs\"").count(), 3);
+        assert!(html.contains("id=\"code-tab-3-0\""));
+        assert!(!html.contains("id=\"code-tab-1-1\""));
+    }
 }
@@ -0,0 +1,325 @@
+/// Parsed info string of a fenced code block.
+///
+/// The format is compatible with rustdoc: the info string is a list of
+/// tokens separated by commas or whitespace, e.g. `rust,no_run` or
+/// `compile_fail`. A block that only contains rustdoc attributes (without any
+/// language) is treated as Rust, just like rustdoc does. Unlike rustdoc,
+/// a block with an empty info string is treated as plain text, because this
+/// is what the guides use for command output and similar snippets.
+///
+/// Additionally, the info string can contain `key=value` metadata (the value
+/// can be quoted if it contains spaces or commas), e.g.
+/// `rust title="src/main.rs" hl_lines="3-5" linenos`, or
+/// `toml group="dependency" tab="Cargo.toml"` for code blocks that are
+/// rendered as tabs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(super) struct CodeBlockInfo {
+    /// The language of the code block, passed to the syntax highlighter.
+    pub(super) lang: String,
+    pub(super) ignore: bool,
+    pub(super) no_run: bool,
+    pub(super) compile_fail: bool,
+    pub(super) should_panic: bool,
+    /// The title of the code block (usually a file name) shown above it.
+    pub(super) title: Option<String>,
+    /// Ranges of (1-based, inclusive) line numbers to highlight.
+    pub(super) hl_lines: Vec<(usize, usize)>,
+    /// Whether to show line numbers.
+    pub(super) linenos: bool,
+    /// The name of the tab group. Consecutive code blocks with the same group
+    /// are rendered as a single tab widget, and all the tab widgets with the
+    /// same group on a page switch tabs together.
+    pub(super) group: Option<String>,
+    /// The label of the tab, if the block is a part of a tab group.
+    pub(super) tab: Option<String>,
+}
+
+impl CodeBlockInfo {
+    pub(super) fn parse(info: &str) -> Result<Self, String> {
+        let mut result = Self::default();
+        let mut lang = None;
+        let mut rustdoc_attribute = false;
+
+        for token in split_info_string(info) {
+            if let Some((key, value)) = token.split_once('=') {
+                let value = value.trim_matches('"');
+                match key {
+                    "title" => result.title = Some(value.to_string()),
+                    "hl_lines" => result.hl_lines = parse_line_ranges(value)?,
+                    "group" => result.group = Some(value.to_string()),
+                    "tab" => result.tab = Some(value.to_string()),
+                    _ => {}
+                }
+                continue;
+            }
+
+            match token {
+                "linenos" => result.linenos = true,
+                "ignore" => {
+                    result.ignore = true;
+                    rustdoc_attribute = true;
+                }
+                "no_run" => {
+                    result.no_run = true;
+                    rustdoc_attribute = true;
+                }
+                "compile_fail" => {
+                    result.compile_fail = true;
+                    rustdoc_attribute = true;
+                }
+                "should_panic" => {
+                    result.should_panic = true;
+                    rustdoc_attribute = true;
+                }
+                token if token.starts_with("ignore-") => {
+                    result.ignore = true;
+                    rustdoc_attribute = true;
+                }
+                token if is_other_rustdoc_attribute(token) => rustdoc_attribute = true,
+                token => {
+                    if lang.is_none() {
+                        lang = Some(token);
+                    }
+                }
+            }
+        }
+
+        result.lang = match lang {
+            Some(lang) => lang.to_string(),
+            // only rustdoc attributes, with no language specified
+            None if rustdoc_attribute => "rust".to_string(),
+            None => String::new(),
+        };
+        Ok(result)
+    }
+
+    pub(super) fn is_rust(&self) -> bool {
+        matches!(self.lang.as_str(), "rust" | "rs")
+    }
+
+    /// Returns the text of the badge to be shown next to the code block, if
+    /// any, along with the badge's CSS modifier.
+    pub(super) fn badge(&self) -> Option<(&'static str, &'static str)> {
+        if self.compile_fail {
+            Some(("does not compile", "compile-fail"))
+        } else if self.should_panic {
+            Some(("panics", "should-panic"))
+        } else if self.no_run {
+            Some(("not run", "no-run"))
+        } else {
+            None
+        }
+    }
+
+    /// Whether the code block needs to be rendered line by line (as opposed
+    /// to letting comrak render it as a whole).
+    pub(super) fn has_line_options(&self) -> bool {
+        self.linenos || !self.hl_lines.is_empty()
+    }
+
+    /// Returns the label of the tab for code blocks that are a part of a tab
+    /// group, falling back to the title and the language name.
+    pub(super) fn tab_label(&self) -> &str {
+        self.tab
+            .as_deref()
+            .or(self.title.as_deref())
+            .unwrap_or(&self.lang)
+    }
+
+    pub(super) fn is_line_highlighted(&self, line: usize) -> bool {
+        self.hl_lines
+            .iter()
+            .any(|&(start, end)| (start..=end).contains(&line))
+    }
+}
+
+/// Splits the info string into tokens separated by commas or whitespace,
+/// except when they're inside double quotes.
+fn split_info_string(info: &str) -> Vec<&str> {
+    let mut tokens = Vec::new();
+    let mut in_quotes = false;
+    let mut start = 0;
+
+    for (index, c) in info.char_indices() {
+        if c == '"' {
+            in_quotes = !in_quotes;
+        } else if !in_quotes && (c == ',' || c.is_whitespace()) {
+            tokens.push(&info[start..index]);
+            start = index + c.len_utf8();
+        }
+    }
+    tokens.push(&info[start..]);
+
+    tokens.retain(|token| !token.is_empty());
+    tokens
+}
+
+/// Parses line ranges in the format used by `hl_lines`, e.g. `1 3-5` or
+/// `1,3-5`.
+fn parse_line_ranges(value: &str) -> Result<Vec<(usize, usize)>, String> {
+    value
+        .split(|c: char| c == ',' || c.is_whitespace())
+        .filter(|range| !range.is_empty())
+        .map(|range| {
+            let (start, end) = range.split_once('-').unwrap_or((range, range));
+            let start = start.trim().parse::<usize>();
+            let end = end.trim().parse::<usize>();
+            match (start, end) {
+                (Ok(start), Ok(end)) if start > 0 && start <= end => Ok((start, end)),
+                _ => Err(format!("invalid line range in `hl_lines`: `{range}`")),
+            }
+        })
+        .collect()
+}
+
+/// Checks whether the token is one of the rustdoc attributes that don't
+/// affect the way the code block is rendered.
+fn is_other_rustdoc_attribute(token: &str) -> bool {
+    matches!(
+        token,
+        "test_harness" | "standalone_crate" | "allow_fail" | "unsafe"
+    ) || token.starts_with("edition")
+        || is_error_code(token)
+}
+
+/// Checks whether the token is a compiler error code, such as `E0277`, which
+/// can be attached to `compile_fail` blocks.
+fn is_error_code(token: &str) -> bool {
+    token.len() == 5 && token.starts_with('E') && token[1..].chars().all(|c| c.is_ascii_digit())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(info: &str) -> CodeBlockInfo {
+        CodeBlockInfo::parse(info).unwrap()
+    }
+
+    #[test]
+    fn test_parse_lang() {
+        assert_eq!(parse("rust").lang, "rust");
+        assert_eq!(parse("toml").lang, "toml");
+        assert_eq!(parse("html.j2").lang, "html.j2");
+        assert_eq!(parse("").lang, "");
+    }
+
+    #[test]
+    fn test_parse_rustdoc_attributes() {
+        let info = parse("rust,ignore");
+        assert_eq!(info.lang, "rust");
+        assert!(info.ignore);
+
+        let info = parse("rust, no_run");
+        assert_eq!(info.lang, "rust");
+        assert!(info.no_run);
+
+        let info = parse("should_panic");
+        assert_eq!(info.lang, "rust");
+        assert!(info.should_panic);
+
+        let info = parse("compile_fail,E0277");
+        assert_eq!(info.lang, "rust");
+        assert!(info.compile_fail);
+
+        let info = parse("ignore-windows edition2021");
+        assert_eq!(info.lang, "rust");
+        assert!(info.ignore);
+    }
+
+    #[test]
+    fn test_parse_other_lang_with_attributes() {
+        let info = parse("text,ignore");
+        assert_eq!(info.lang, "text");
+        assert!(info.ignore);
+        assert!(!info.is_rust());
+    }
+
+    #[test]
+    fn test_badge() {
+        assert_eq!(parse("rust").badge(), None);
+        assert_eq!(parse("rust,ignore").badge(), None);
+        assert_eq!(
+            parse("rust,compile_fail").badge(),
+            Some(("does not compile", "compile-fail"))
+        );
+        assert_eq!(
+            parse("should_panic").badge(),
+            Some(("panics", "should-panic"))
+        );
+        assert_eq!(parse("no_run").badge(), Some(("not run", "no-run")));
+    }
+
+    #[test]
+    fn test_parse_metadata() {
+        let info = parse(r#"rust title="src/main.rs" hl_lines="3-5" linenos"#);
+        assert_eq!(info.lang, "rust");
+        assert_eq!(info.title.as_deref(), Some("src/main.rs"));
+        assert_eq!(info.hl_lines, vec![(3, 5)]);
+        assert!(info.linenos);
+        assert!(info.has_line_options());
+    }
+
+    #[test]
+    fn test_parse_metadata_quoted_separators() {
+        let info = parse(r#"toml,title="Cargo.toml, workspace root",hl_lines="1,3-4""#);
+        assert_eq!(info.lang, "toml");
+        assert_eq!(info.title.as_deref(), Some("Cargo.toml, workspace root"));
+        assert_eq!(info.hl_lines, vec![(1, 1), (3, 4)]);
+        assert!(!info.linenos);
+    }
+
+    #[test]
+    fn test_parse_metadata_without_lang() {
+        let info = parse(r#"no_run title="main.rs""#);
+        assert_eq!(info.lang, "rust");
+        assert!(info.no_run);
+        assert_eq!(info.title.as_deref(), Some("main.rs"));
+        assert!(!info.has_line_options());
+    }
+
+    #[test]
+    fn test_parse_metadata_only() {
+        for info in [
+            r#"title="Cargo.toml""#,
+            "group=setup tab=CLI",
+            r#"hl_lines="1" linenos"#,
+        ] {
+            assert_eq!(parse(info).lang, "", "{info}");
+        }
+    }
+
+    #[test]
+    fn test_parse_invalid_hl_lines() {
+        assert!(CodeBlockInfo::parse(r#"rust hl_lines="5-3""#).is_err());
+        assert!(CodeBlockInfo::parse(r#"rust hl_lines="0""#).is_err());
+        assert!(CodeBlockInfo::parse(r#"rust hl_lines="a-b""#).is_err());
+    }
+
+    #[test]
+    fn test_is_line_highlighted() {
+        let info = parse(r#"rust hl_lines="1 3-5""#);
+        assert!(info.is_line_highlighted(1));
+        assert!(!info.is_line_highlighted(2));
+        assert!(info.is_line_highlighted(3));
+        assert!(info.is_line_highlighted(5));
+        assert!(!info.is_line_highlighted(6));
+    }
+
+    #[test]
+    fn test_parse_tab_group() {
+        let info = parse(r#"toml group="dependency" tab="Cargo.toml""#);
+        assert_eq!(info.lang, "toml");
+        assert_eq!(info.group.as_deref(), Some("dependency"));
+        assert_eq!(info.tab_label(), "Cargo.toml");
+    }
+
+    #[test]
+    fn test_tab_label_fallback() {
+        assert_eq!(
+            parse(r#"toml group="db" title="config/dev.toml""#).tab_label(),
+            "config/dev.toml"
+        );
+        assert_eq!(parse(r#"bash group="db""#).tab_label(), "bash");
+    }
+}
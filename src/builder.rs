@@ -0,0 +1,207 @@
+//! The builder of [`CotSiteApp`], which makes it possible to serve the guide
+//! of any project, with cot.rs being just one configuration of it.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use cot_site_common::md_pages::MdPage;
+use cot_site_common::{ALL_VERSIONS, LATEST_VERSION, MASTER_VERSION};
+use cot_site_macros::all_guide_manifests;
+
+use crate::guides::{ParsedPages, parse_guides};
+use crate::search::{AppSearchIndex, DEFAULT_SEARCH_INDEX_DIR};
+use crate::site_config::SiteConfig;
+use crate::site_pages::{
+    RESERVED_PAGE_NAMES, StandalonePage, faq_page, licenses_page, searchable_site_pages,
+};
+use crate::social_card::SocialCards;
+use crate::text_search::TextSearch;
+use crate::{BaseContext, CotSiteApp, GuideItem};
+
+/// The navigation of a version of the guide: a list of categories, each with
+/// its name and the pages inside it.
+pub type GuideNavigation = Vec<(&'static str, Vec<GuideItem>)>;
+
+/// Replacements for the built-in templates of the site. Each of them renders
+/// the whole page, usually with a custom [`askama`] template, and gets the
+/// same context as the template it replaces.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TemplateOverrides {
+    /// Renders the home page.
+    pub index: Option<fn(&BaseContext) -> cot::Result<String>>,
+    /// Renders the standalone pages (see [`StandalonePage`]).
+    pub standalone_page: Option<fn(&BaseContext, &MdPage) -> cot::Result<String>>,
+}
+
+/// A builder for [`CotSiteApp`], created with [`CotSiteApp::builder`].
+///
+/// # Examples
+/// The pages are usually created from the Markdown files with the
+/// [`guide_manifest!`](crate::guide_manifest) and [`md_page!`](crate::md_page)
+/// macros.
+///
+/// ```no_run
+/// use cot_site::cot_site_common::md_pages::MdPage;
+/// use cot_site::{CotSiteApp, GuideItem, SiteConfig, StandalonePage};
+///
+/// let introduction = || {
+///     let page = MdPage::new("introduction", "Introduction", "<p>Hi!</p>");
+///     vec![("Getting started", vec![GuideItem::Page(page)])]
+/// };
+/// let app = CotSiteApp::builder()
+///     .version("v1.0", introduction())
+///     .version("v0.9", introduction())
+///     .latest_version("v1.0")
+///     .standalone_page(StandalonePage::new(
+///         "faq",
+///         MdPage::new("faq", "FAQ", "<p>Questions and answers</p>"),
+///     ))
+///     .site_config(SiteConfig {
+///         base_url: "https://docs.example.com".to_string(),
+///         name: "Example".to_string(),
+///         ..SiteConfig::default()
+///     })
+///     .build();
+/// ```
+#[derive(Default)]
+pub struct CotSiteAppBuilder {
+    versions: Vec<(&'static str, GuideNavigation)>,
+    latest_version: Option<&'static str>,
+    standalone_pages: Vec<StandalonePage>,
+    site_config: SiteConfig,
+    templates: TemplateOverrides,
+}
+
+impl CotSiteAppBuilder {
+    /// Creates a builder configured for <https://cot.rs>: the guide of all the
+    /// Cot versions in `docs/` plus the unreleased `master` version, the FAQ
+    /// and the licenses pages.
+    ///
+    /// The `master_pages` parameter should contain the navigation of the
+    /// `master` version. It's usually generated from the `SUMMARY.md` file of
+    /// the guide with the [`guide_manifest!`](crate::guide_manifest) macro.
+    pub fn cot_rs(master_pages: GuideNavigation) -> Self {
+        let mut released_versions: Vec<_> = all_guide_manifests!().into_iter().collect();
+        released_versions
+            .sort_by_key(|&(version, _)| ALL_VERSIONS.iter().position(|&v| v == version));
+
+        let mut builder = Self::default().version(MASTER_VERSION, master_pages);
+        for (version, pages) in released_versions {
+            builder = builder.version(version, pages);
+        }
+        builder
+            .latest_version(LATEST_VERSION)
+            .standalone_page(StandalonePage::new("faq", faq_page()))
+            .standalone_page(
+                StandalonePage::new("licenses", licenses_page()).with_sitemap_priority(0.1),
+            )
+    }
+
+    /// Adds a version of the guide with the given navigation. The versions
+    /// have to be added from the newest to the oldest, as this is the order
+    /// they are listed in.
+    #[must_use]
+    pub fn version(mut self, name: &'static str, navigation: GuideNavigation) -> Self {
+        self.versions.push((name, navigation));
+        self
+    }
+
+    /// Sets the version that `latest` in the URLs refers to. The versions
+    /// added before it are considered unreleased. By default, it's the first
+    /// version added.
+    #[must_use]
+    pub fn latest_version(mut self, name: &'static str) -> Self {
+        self.latest_version = Some(name);
+        self
+    }
+
+    /// Adds a markdown page outside of the guide.
+    #[must_use]
+    pub fn standalone_page(mut self, page: StandalonePage) -> Self {
+        self.standalone_pages.push(page);
+        self
+    }
+
+    /// Sets the address and the metadata of the site (by default, the ones
+    /// of <https://cot.rs>).
+    #[must_use]
+    pub fn site_config(mut self, site_config: SiteConfig) -> Self {
+        self.site_config = site_config;
+        self
+    }
+
+    /// Replaces some of the built-in templates.
+    #[must_use]
+    pub fn templates(mut self, templates: TemplateOverrides) -> Self {
+        self.templates = templates;
+        self
+    }
+
+    /// Builds the app.
+    ///
+    /// # Panics
+    ///
+    /// Panics if no versions have been added, if the latest version is not
+    /// one of them, or if a standalone page has the name of a built-in route
+    /// (e.g. `search`) or the same name as another standalone page.
+    pub fn build(self) -> CotSiteApp {
+        let latest_version = self
+            .latest_version
+            .or_else(|| self.versions.first().map(|&(version, _)| version))
+            .expect("at least one version of the guide should be added");
+        assert!(
+            self.versions
+                .iter()
+                .any(|&(version, _)| version == latest_version),
+            "the latest version ({latest_version}) should be one of the versions of the guide"
+        );
+        for (index, page) in self.standalone_pages.iter().enumerate() {
+            assert!(
+                !RESERVED_PAGE_NAMES.contains(&page.name) && !page.name.starts_with("guide_page_"),
+                "the standalone page name `{}` is reserved for a built-in route",
+                page.name
+            );
+            assert!(
+                self.standalone_pages[..index]
+                    .iter()
+                    .all(|other| other.name != page.name),
+                "there should be only one standalone page named `{}`",
+                page.name
+            );
+        }
+
+        let versions = self
+            .versions
+            .into_iter()
+            .map(|(version, navigation)| (version, parse_guides(navigation)))
+            .collect();
+        let pages = ParsedPages::new(versions, latest_version);
+        // like the draft guide pages, the draft standalone pages are not served
+        let standalone_pages: Arc<[_]> = self
+            .standalone_pages
+            .into_iter()
+            .filter(|page| !page.page.draft)
+            .collect();
+        let site_pages: Arc<[_]> =
+            searchable_site_pages(&self.site_config, &standalone_pages).into();
+        let text_search = TextSearch::new(&pages, &site_pages);
+        let pages = Arc::new(pages);
+        let search_index = AppSearchIndex::new(
+            PathBuf::from(DEFAULT_SEARCH_INDEX_DIR),
+            Arc::clone(&pages),
+            Arc::clone(&site_pages),
+        );
+
+        CotSiteApp {
+            pages,
+            site_pages,
+            standalone_pages,
+            text_search: Arc::new(text_search),
+            social_cards: Arc::new(SocialCards::new()),
+            search_index: Arc::new(search_index),
+            site_config: Arc::new(self.site_config),
+            templates: self.templates,
+            other_versions_in_sitemap: true,
+        }
+    }
+}
@@ -1,41 +1,89 @@
 use std::collections::HashMap;
 
 use cot_site_common::md_pages::{MdPage, MdPageLink};
-use cot_site_macros::md_page;
 
 use crate::{GuideCategoryItem, GuideItem, GuideLinkCategory};
 
+/// Parses the navigation of a version of the guide. The draft pages are left
+/// out, so they are not served, searchable or listed in the sitemap.
 pub fn parse_guides(categories: Vec<(&'static str, Vec<GuideItem>)>) -> ParsedPagesForVersion {
+    let categories: Vec<_> = categories
+        .into_iter()
+        .map(|(title, items)| (title, remove_drafts(items)))
+        .filter(|(_title, items)| !items.is_empty())
+        .collect();
+
     let categories_links = categories
         .iter()
         .map(|(title, items)| GuideLinkCategory {
             title,
             guides: items
                 .iter()
-                .map(|item| match item {
-                    GuideItem::Page(page) => GuideCategoryItem::Page(MdPageLink::from(page)),
-                    GuideItem::SubCategory { title, pages } => GuideCategoryItem::SubCategory {
-                        title,
-                        pages: pages.iter().map(MdPageLink::from).collect(),
-                    },
-                })
+                .map(|item| to_category_item(item, None))
                 .collect(),
         })
         .collect();
 
-    let guide_map = categories
+    let guide_map: HashMap<String, MdPage> = categories
         .into_iter()
         .flat_map(|(_title, items)| items)
-        .flat_map(|item| match item {
-            GuideItem::Page(page) => vec![page],
-            GuideItem::SubCategory { pages, .. } => pages,
-        })
+        .flat_map(GuideItem::into_pages)
         .map(|page| (page.link.clone(), page))
         .collect();
 
+    let alias_map = guide_map
+        .values()
+        .flat_map(|page| {
+            page.aliases
+                .iter()
+                .map(|alias| (alias.clone(), page.link.clone()))
+        })
+        .collect();
+
     ParsedPagesForVersion {
         categories_links,
         guide_map,
+        alias_map,
+        version_links: HashMap::new(),
+    }
+}
+
+/// Removes the draft pages from the items, along with the subcategories that
+/// only contain drafts.
+fn remove_drafts(items: Vec<GuideItem>) -> Vec<GuideItem> {
+    items
+        .into_iter()
+        .filter_map(|item| match item {
+            GuideItem::Page(page) if page.draft => None,
+            GuideItem::Page(page) => Some(GuideItem::Page(page)),
+            GuideItem::SubCategory { title, items } => {
+                let items = remove_drafts(items);
+                (!items.is_empty()).then_some(GuideItem::SubCategory { title, items })
+            }
+        })
+        .collect()
+}
+
+/// Converts a guide item to its representation used in the templates.
+/// `parent_collapse_id` is the collapse ID of the parent subcategory, if any.
+fn to_category_item(item: &GuideItem, parent_collapse_id: Option<&str>) -> GuideCategoryItem {
+    match item {
+        GuideItem::Page(page) => GuideCategoryItem::Page(MdPageLink::from(page)),
+        GuideItem::SubCategory { title, items } => {
+            let id = title.to_lowercase().replace(' ', "-");
+            let collapse_id = match parent_collapse_id {
+                Some(parent_collapse_id) => format!("{parent_collapse_id}-{id}"),
+                None => id,
+            };
+            GuideCategoryItem::SubCategory {
+                title,
+                items: items
+                    .iter()
+                    .map(|item| to_category_item(item, Some(&collapse_id)))
+                    .collect(),
+                collapse_id,
+            }
+        }
     }
 }
 
@@ -43,13 +91,187 @@ pub fn parse_guides(categories: Vec<(&'static str, Vec<GuideItem>)>) -> ParsedPa
 pub(crate) struct ParsedPagesForVersion {
     pub(crate) categories_links: Vec<GuideLinkCategory>,
     pub(crate) guide_map: HashMap<String, MdPage>,
+    /// Maps the aliases of the pages to the links of the pages.
+    pub(crate) alias_map: HashMap<String, String>,
+    /// Maps the links of the pages to the links to their equivalents in all
+    /// the versions of the guide.
+    pub(crate) version_links: HashMap<String, Vec<VersionLink>>,
+}
+
+impl ParsedPagesForVersion {
+    /// Finds the page in this version that is the equivalent of the given page
+    /// (possibly coming from another version): the page with the same link, or
+    /// a page that lists the link as one of its aliases. The aliases of the
+    /// given page are checked the same way if that fails.
+    fn find_equivalent(&self, page: &MdPage) -> Option<&str> {
+        std::iter::once(&page.link)
+            .chain(&page.aliases)
+            .find_map(|link| {
+                self.guide_map
+                    .get_key_value(link)
+                    .map(|(link, _page)| link)
+                    .or_else(|| self.alias_map.get(link))
+            })
+            .map(String::as_str)
+    }
+}
+
+/// A link to the equivalent of a page in a specific version of the guide.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct VersionLink {
+    pub(crate) version: &'static str,
+    /// The link of the equivalent page, or `None` if there isn't one and the
+    /// root of the version should be used instead.
+    pub(crate) page: Option<String>,
 }
 
 #[derive(Debug)]
 pub(crate) struct ParsedPages {
+    /// All the versions of the guide, from the newest (usually the unreleased
+    /// one) to the oldest.
+    pub(crate) versions: Vec<&'static str>,
+    /// The version that `latest` in the URLs refers to.
+    pub(crate) latest_version: &'static str,
     pub(crate) version_map: HashMap<&'static str, ParsedPagesForVersion>,
 }
 
+impl ParsedPages {
+    /// Creates the pages of all the versions of the guide, which are given
+    /// from the newest to the oldest.
+    pub(crate) fn new(
+        versions: Vec<(&'static str, ParsedPagesForVersion)>,
+        latest_version: &'static str,
+    ) -> Self {
+        let mut pages = Self {
+            versions: versions.iter().map(|&(version, _)| version).collect(),
+            latest_version,
+            version_map: versions.into_iter().collect(),
+        };
+        pages.compute_version_links();
+        pages
+    }
+
+    /// Returns the version with the given name, resolving `latest` to the
+    /// latest version, or `None` if there is no such version.
+    pub(crate) fn resolve_version(&self, version: &str) -> Option<&'static str> {
+        let version = if version == "latest" {
+            self.latest_version
+        } else {
+            version
+        };
+        self.versions.iter().copied().find(|&v| v == version)
+    }
+
+    /// Returns whether the version is newer than the latest one, i.e. it
+    /// hasn't been released yet.
+    pub(crate) fn is_unreleased(&self, version: &str) -> bool {
+        self.unreleased_versions().contains(&version)
+    }
+
+    /// Returns the versions newer than the latest one, from the newest to the
+    /// oldest.
+    pub(crate) fn unreleased_versions(&self) -> &[&'static str] {
+        &self.versions[..self.latest_version_index()]
+    }
+
+    /// Returns the released versions, from the latest to the oldest.
+    pub(crate) fn released_versions(&self) -> &[&'static str] {
+        &self.versions[self.latest_version_index()..]
+    }
+
+    fn latest_version_index(&self) -> usize {
+        self.versions
+            .iter()
+            .position(|&v| v == self.latest_version)
+            .unwrap_or_default()
+    }
+
+    /// Computes the links to the equivalents of every page in all the versions,
+    /// ordered as in [`Self::versions`], so that the version switcher can keep
+    /// the reader on the same page.
+    fn compute_version_links(&mut self) {
+        let all_version_links: Vec<(&'static str, HashMap<String, Vec<VersionLink>>)> = self
+            .version_map
+            .iter()
+            .map(|(&version, pages)| {
+                let version_links = pages
+                    .guide_map
+                    .values()
+                    .map(|page| (page.link.clone(), self.version_links_for(page)))
+                    .collect();
+                (version, version_links)
+            })
+            .collect();
+
+        for (version, version_links) in all_version_links {
+            if let Some(pages) = self.version_map.get_mut(version) {
+                pages.version_links = version_links;
+            }
+        }
+    }
+
+    /// Finds the version nearest to `version` that contains the page with the
+    /// given link (or a page that lists the link as one of its aliases), and
+    /// returns it along with the link of the page in that version.
+    ///
+    /// The distance between versions is measured by their position in
+    /// [`Self::versions`]. When two versions are equally close, the newer one
+    /// is preferred. The unreleased versions are only considered if `version`
+    /// is unreleased itself, so that the readers of a released version are
+    /// not sent to the documentation of features they don't have yet.
+    pub(crate) fn find_nearest_version(
+        &self,
+        version: &str,
+        link: &str,
+    ) -> Option<(&'static str, &str)> {
+        let position = self.versions.iter().position(|&v| v == version)?;
+        let include_unreleased = self.is_unreleased(version);
+
+        self.versions
+            .iter()
+            .enumerate()
+            .filter(|&(index, _)| index != position)
+            .filter(|&(_, candidate)| include_unreleased || !self.is_unreleased(candidate))
+            .filter_map(|(index, &candidate)| {
+                let pages = self.version_map.get(candidate)?;
+                let link = pages
+                    .guide_map
+                    .get_key_value(link)
+                    .map(|(link, _page)| link)
+                    .or_else(|| pages.alias_map.get(link))?;
+                Some((index.abs_diff(position), index, candidate, link.as_str()))
+            })
+            .min_by_key(|&(distance, index, _, _)| (distance, index))
+            .map(|(_, _, candidate, link)| (candidate, link))
+    }
+
+    /// Returns the number of path segments of the most deeply nested page in
+    /// any version (at least 2, so that the routes for the pages inside a
+    /// section always exist).
+    pub(crate) fn max_page_depth(&self) -> usize {
+        self.version_map
+            .values()
+            .flat_map(|pages| pages.guide_map.keys())
+            .map(|link| link.split('/').count())
+            .max()
+            .unwrap_or_default()
+            .max(2)
+    }
+
+    fn version_links_for(&self, page: &MdPage) -> Vec<VersionLink> {
+        self.versions
+            .iter()
+            .filter_map(|&version| {
+                let pages = self.version_map.get(version)?;
+                Some(VersionLink {
+                    version,
+                    page: pages.find_equivalent(page).map(str::to_owned),
+                })
+            })
+            .collect()
+    }
+}
+
 pub fn get_prev_next_link<'a>(
     guides: &'a [GuideLinkCategory],
     current_id: &str,
@@ -57,10 +279,7 @@ pub fn get_prev_next_link<'a>(
     let all_links: Vec<&MdPageLink> = guides
         .iter()
         .flat_map(|category| category.guides.iter())
-        .flat_map(|item| match item {
-            GuideCategoryItem::Page(link) => vec![link],
-            GuideCategoryItem::SubCategory { pages, .. } => pages.iter().collect(),
-        })
+        .flat_map(GuideCategoryItem::links)
         .collect();
 
     let mut prev = None;
@@ -79,179 +298,318 @@ pub fn get_prev_next_link<'a>(
     (prev, None)
 }
 
-pub fn get_categories(master_version: Vec<(&'static str, Vec<GuideItem>)>) -> ParsedPages {
-    let version_map = HashMap::from([
-        (
-            "v0.1",
-            vec![(
-                "Getting started",
-                vec![
-                    GuideItem::Page(md_page!("v0.1", "introduction")),
-                    GuideItem::Page(md_page!("v0.1", "templates")),
-                    GuideItem::Page(md_page!("v0.1", "forms")),
-                    GuideItem::Page(md_page!("v0.1", "db-models")),
-                    GuideItem::Page(md_page!("v0.1", "admin-panel")),
-                    GuideItem::Page(md_page!("v0.1", "static-files")),
-                    GuideItem::Page(md_page!("v0.1", "error-pages")),
-                    GuideItem::Page(md_page!("v0.1", "testing")),
-                ],
-            )],
-        ),
-        (
-            "v0.2",
-            vec![(
-                "Getting started",
-                vec![
-                    GuideItem::Page(md_page!("v0.2", "introduction")),
-                    GuideItem::Page(md_page!("v0.2", "templates")),
-                    GuideItem::Page(md_page!("v0.2", "forms")),
-                    GuideItem::Page(md_page!("v0.2", "db-models")),
-                    GuideItem::Page(md_page!("v0.2", "admin-panel")),
-                    GuideItem::Page(md_page!("v0.2", "static-files")),
-                    GuideItem::Page(md_page!("v0.2", "error-pages")),
-                    GuideItem::Page(md_page!("v0.2", "testing")),
-                ],
-            )],
-        ),
-        (
-            "v0.3",
-            vec![(
-                "Getting started",
-                vec![
-                    GuideItem::Page(md_page!("v0.3", "introduction")),
-                    GuideItem::Page(md_page!("v0.3", "templates")),
-                    GuideItem::Page(md_page!("v0.3", "forms")),
-                    GuideItem::Page(md_page!("v0.3", "db-models")),
-                    GuideItem::Page(md_page!("v0.3", "admin-panel")),
-                    GuideItem::Page(md_page!("v0.3", "static-files")),
-                    GuideItem::Page(md_page!("v0.3", "error-pages")),
-                    GuideItem::Page(md_page!("v0.3", "openapi")),
-                    GuideItem::Page(md_page!("v0.3", "testing")),
-                ],
-            )],
-        ),
-        (
-            "v0.4",
+/// Returns the title of the top-level category containing the page.
+pub(crate) fn get_category(guides: &[GuideLinkCategory], current_id: &str) -> Option<&'static str> {
+    guides
+        .iter()
+        .find(|category| {
+            category
+                .guides
+                .iter()
+                .flat_map(GuideCategoryItem::links)
+                .any(|link| link.link == current_id)
+        })
+        .map(|category| category.title)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VERSIONS: [&str; 3] = ["master", "v0.2", "v0.1"];
+
+    fn page(link: &str, aliases: &[&str]) -> MdPage {
+        MdPage {
+            aliases: aliases.iter().map(|alias| alias.to_string()).collect(),
+            ..MdPage::new(link, link, "")
+        }
+    }
+
+    fn pages(items: Vec<MdPage>) -> ParsedPagesForVersion {
+        parse_guides(vec![(
+            "Getting started",
+            items.into_iter().map(GuideItem::Page).collect(),
+        )])
+    }
+
+    fn parsed_pages() -> ParsedPages {
+        ParsedPages::new(
             vec![
                 (
-                    "Getting started",
-                    vec![
-                        GuideItem::Page(md_page!("v0.4", "introduction")),
-                        GuideItem::Page(md_page!("v0.4", "templates")),
-                        GuideItem::Page(md_page!("v0.4", "forms")),
-                        GuideItem::Page(md_page!("v0.4", "db-models")),
-                        GuideItem::Page(md_page!("v0.4", "admin-panel")),
-                        GuideItem::Page(md_page!("v0.4", "static-files")),
-                        GuideItem::Page(md_page!("v0.4", "error-pages")),
-                        GuideItem::Page(md_page!("v0.4", "openapi")),
-                        GuideItem::Page(md_page!("v0.4", "testing")),
-                    ],
+                    VERSIONS[1],
+                    pages(vec![
+                        page("introduction", &[]),
+                        page("databases/overview", &["db-models"]),
+                        page("caching", &[]),
+                    ]),
                 ),
                 (
-                    "Upgrading",
-                    vec![GuideItem::Page(md_page!("v0.4", "upgrade-guide"))],
+                    VERSIONS[2],
+                    pages(vec![page("introduction", &[]), page("db-models", &[])]),
                 ),
             ],
-        ),
-        (
-            "v0.5",
+            VERSIONS[1],
+        )
+    }
+
+    fn version_links(pages: &ParsedPages, version: &str, page: &str) -> Vec<Option<String>> {
+        pages.version_map[version].version_links[page]
+            .iter()
+            .map(|version_link| version_link.page.clone())
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_guides_skips_drafts() {
+        let draft = |link| MdPage {
+            draft: true,
+            ..page(link, &[])
+        };
+        let pages = parse_guides(vec![
+            (
+                "Getting started",
+                vec![
+                    GuideItem::Page(page("introduction", &[])),
+                    GuideItem::Page(draft("caching")),
+                    GuideItem::SubCategory {
+                        title: "Drafts",
+                        items: vec![GuideItem::Page(draft("databases/overview"))],
+                    },
+                ],
+            ),
+            ("Upcoming", vec![GuideItem::Page(draft("openapi"))]),
+        ]);
+
+        assert_eq!(pages.guide_map.keys().collect::<Vec<_>>(), ["introduction"]);
+        assert_eq!(pages.categories_links.len(), 1);
+        assert_eq!(pages.categories_links[0].guides.len(), 1);
+    }
+
+    #[test]
+    fn test_version_links_same_page() {
+        let pages = parsed_pages();
+
+        assert_eq!(
+            version_links(&pages, VERSIONS[1], "introduction"),
+            vec![Some("introduction".to_string()); 2]
+        );
+    }
+
+    #[test]
+    fn test_version_links_alias() {
+        let pages = parsed_pages();
+
+        assert_eq!(
+            version_links(&pages, VERSIONS[1], "databases/overview"),
             vec![
-                (
-                    "Getting started",
-                    vec![
-                        GuideItem::Page(md_page!("v0.5", "introduction")),
-                        GuideItem::Page(md_page!("v0.5", "templates")),
-                        GuideItem::Page(md_page!("v0.5", "forms")),
-                        GuideItem::Page(md_page!("v0.5", "db-models")),
-                        GuideItem::Page(md_page!("v0.5", "admin-panel")),
-                        GuideItem::Page(md_page!("v0.5", "static-files")),
-                        GuideItem::Page(md_page!("v0.5", "sending-emails")),
-                        GuideItem::Page(md_page!("v0.5", "caching")),
-                        GuideItem::Page(md_page!("v0.5", "error-pages")),
-                        GuideItem::Page(md_page!("v0.5", "openapi")),
-                        GuideItem::Page(md_page!("v0.5", "testing")),
-                    ],
-                ),
-                (
-                    "Upgrading",
-                    vec![GuideItem::Page(md_page!("v0.5", "upgrade-guide"))],
-                ),
-                (
-                    "About",
-                    vec![GuideItem::Page(md_page!("v0.5", "framework-comparison"))],
-                ),
+                Some("databases/overview".to_string()),
+                Some("db-models".to_string())
+            ]
+        );
+        assert_eq!(
+            version_links(&pages, VERSIONS[2], "db-models"),
+            vec![
+                Some("databases/overview".to_string()),
+                Some("db-models".to_string())
+            ]
+        );
+    }
+
+    #[test]
+    fn test_version_links_missing_page() {
+        let pages = parsed_pages();
+
+        assert_eq!(
+            version_links(&pages, VERSIONS[1], "caching"),
+            vec![Some("caching".to_string()), None]
+        );
+    }
+
+    #[test]
+    fn test_find_nearest_version() {
+        let pages = parsed_pages();
+
+        assert_eq!(
+            pages.find_nearest_version(VERSIONS[2], "caching"),
+            Some((VERSIONS[1], "caching"))
+        );
+        assert_eq!(
+            pages.find_nearest_version(VERSIONS[2], "databases/overview"),
+            Some((VERSIONS[1], "databases/overview"))
+        );
+        assert_eq!(
+            pages.find_nearest_version(VERSIONS[1], "db-models"),
+            Some((VERSIONS[2], "db-models"))
+        );
+        assert_eq!(pages.find_nearest_version(VERSIONS[1], "missing"), None);
+        assert_eq!(pages.find_nearest_version("v0.0", "caching"), None);
+    }
+
+    #[test]
+    fn test_find_nearest_version_prefers_closer_and_newer() {
+        let pages = ParsedPages::new(
+            vec![
+                (VERSIONS[0], pages(vec![page("introduction", &[])])),
+                (VERSIONS[1], pages(vec![page("caching", &[])])),
+                (VERSIONS[2], pages(vec![page("introduction", &[])])),
+                ("v0.0", pages(vec![page("caching", &[])])),
             ],
-        ),
-        (
-            "v0.6",
+            VERSIONS[1],
+        );
+
+        assert_eq!(
+            pages.find_nearest_version(VERSIONS[2], "caching"),
+            Some((VERSIONS[1], "caching"))
+        );
+    }
+
+    #[test]
+    fn test_find_nearest_version_skips_unreleased() {
+        let pages = ParsedPages::new(
             vec![
-                (
-                    "Getting started",
-                    vec![
-                        GuideItem::Page(md_page!("v0.6", "introduction")),
-                        GuideItem::Page(md_page!("v0.6", "templates")),
-                        GuideItem::Page(md_page!("v0.6", "forms")),
-                        GuideItem::Page(md_page!("v0.6", "db-models")),
-                        GuideItem::Page(md_page!("v0.6", "admin-panel")),
-                        GuideItem::Page(md_page!("v0.6", "static-files")),
-                        GuideItem::Page(md_page!("v0.6", "sending-emails")),
-                        GuideItem::Page(md_page!("v0.6", "caching")),
-                        GuideItem::Page(md_page!("v0.6", "error-pages")),
-                        GuideItem::Page(md_page!("v0.6", "openapi")),
-                        GuideItem::Page(md_page!("v0.6", "testing")),
-                    ],
-                ),
-                (
-                    "Upgrading",
-                    vec![GuideItem::Page(md_page!("v0.6", "upgrade-guide"))],
-                ),
-                (
-                    "About",
-                    vec![GuideItem::Page(md_page!("v0.6", "framework-comparison"))],
-                ),
+                (VERSIONS[0], pages(vec![page("caching", &[])])),
+                (VERSIONS[1], pages(vec![page("introduction", &[])])),
+                (VERSIONS[2], pages(vec![page("caching", &[])])),
             ],
-        ),
-        (
-            "v0.7",
+            VERSIONS[1],
+        );
+
+        assert_eq!(
+            pages.find_nearest_version(VERSIONS[1], "caching"),
+            Some((VERSIONS[2], "caching"))
+        );
+    }
+
+    #[test]
+    fn test_find_nearest_version_from_unreleased() {
+        let pages = ParsedPages::new(
             vec![
-                (
-                    "Getting started",
-                    vec![
-                        GuideItem::Page(md_page!("v0.7", "introduction")),
-                        GuideItem::Page(md_page!("v0.7", "templates")),
-                        GuideItem::Page(md_page!("v0.7", "forms")),
+                (VERSIONS[0], pages(vec![page("introduction", &[])])),
+                ("v0.3", pages(vec![page("caching", &[])])),
+                (VERSIONS[1], pages(vec![page("introduction", &[])])),
+                (VERSIONS[2], pages(vec![page("caching", &[])])),
+            ],
+            VERSIONS[1],
+        );
+
+        assert_eq!(
+            pages.find_nearest_version(VERSIONS[0], "caching"),
+            Some(("v0.3", "caching"))
+        );
+    }
+
+    #[test]
+    fn test_released_and_unreleased_versions() {
+        let pages = ParsedPages::new(
+            VERSIONS
+                .iter()
+                .map(|&version| (version, pages(vec![page("introduction", &[])])))
+                .collect(),
+            VERSIONS[1],
+        );
+
+        assert_eq!(pages.unreleased_versions(), &VERSIONS[..1]);
+        assert_eq!(pages.released_versions(), &VERSIONS[1..]);
+        assert!(pages.is_unreleased(VERSIONS[0]));
+        assert!(!pages.is_unreleased(VERSIONS[1]));
+    }
+
+    fn nested_pages() -> ParsedPagesForVersion {
+        parse_guides(vec![(
+            "Getting started",
+            vec![
+                GuideItem::Page(page("introduction", &[])),
+                GuideItem::SubCategory {
+                    title: "Database",
+                    items: vec![
+                        GuideItem::Page(page("databases/overview", &[])),
                         GuideItem::SubCategory {
-                            title: "Database",
-                            pages: vec![
-                                md_page!("v0.7", "databases/overview"),
-                                md_page!("v0.7", "databases/queries"),
-                            ],
+                            title: "Backends",
+                            items: vec![GuideItem::Page(page("databases/backends/postgres", &[]))],
                         },
-                        GuideItem::Page(md_page!("v0.7", "admin-panel")),
-                        GuideItem::Page(md_page!("v0.7", "static-files")),
-                        GuideItem::Page(md_page!("v0.7", "sending-emails")),
-                        GuideItem::Page(md_page!("v0.7", "caching")),
-                        GuideItem::Page(md_page!("v0.7", "error-pages")),
-                        GuideItem::Page(md_page!("v0.7", "openapi")),
-                        GuideItem::Page(md_page!("v0.7", "testing")),
                     ],
-                ),
-                (
-                    "Upgrading",
-                    vec![GuideItem::Page(md_page!("v0.7", "upgrade-guide"))],
-                ),
-                (
-                    "About",
-                    vec![GuideItem::Page(md_page!("v0.7", "framework-comparison"))],
-                ),
+                },
+                GuideItem::Page(page("testing", &[])),
             ],
-        ),
-        ("master", master_version),
-    ]);
+        )])
+    }
 
-    let version_map = version_map
-        .into_iter()
-        .map(|(version, pages)| (version, parse_guides(pages)))
-        .collect();
-    ParsedPages { version_map }
+    #[test]
+    fn test_parse_guides_nested() {
+        let pages = nested_pages();
+
+        assert!(pages.guide_map.contains_key("databases/backends/postgres"));
+        let database = &pages.categories_links[0].guides[1];
+        assert_eq!(database.collapse_id(), "database");
+        assert!(database.contains_active_page("databases/backends/postgres"));
+        assert!(!database.contains_active_page("testing"));
+
+        let GuideCategoryItem::SubCategory { items, .. } = database else {
+            panic!("expected a subcategory");
+        };
+        assert_eq!(items[1].collapse_id(), "database-backends");
+    }
+
+    #[test]
+    fn test_get_prev_next_link_nested() {
+        let pages = nested_pages();
+
+        let (prev, next) =
+            get_prev_next_link(&pages.categories_links, "databases/backends/postgres");
+        assert_eq!(prev.unwrap().link, "databases/overview");
+        assert_eq!(next.unwrap().link, "testing");
+
+        let (prev, next) = get_prev_next_link(&pages.categories_links, "introduction");
+        assert!(prev.is_none());
+        assert_eq!(next.unwrap().link, "databases/overview");
+    }
+
+    #[test]
+    fn test_get_category_nested() {
+        let pages = nested_pages();
+
+        assert_eq!(
+            get_category(&pages.categories_links, "databases/backends/postgres"),
+            Some("Getting started")
+        );
+        assert_eq!(get_category(&pages.categories_links, "missing"), None);
+    }
+
+    #[test]
+    fn test_resolve_version() {
+        let pages = ParsedPages::new(
+            vec![
+                (VERSIONS[0], pages(vec![])),
+                (VERSIONS[1], pages(vec![])),
+                (VERSIONS[2], pages(vec![])),
+            ],
+            VERSIONS[1],
+        );
+
+        assert_eq!(pages.resolve_version("latest"), Some(VERSIONS[1]));
+        assert_eq!(pages.resolve_version(VERSIONS[2]), Some(VERSIONS[2]));
+        assert_eq!(pages.resolve_version("v0.0"), None);
+        assert!(pages.is_unreleased(VERSIONS[0]));
+        assert!(!pages.is_unreleased(VERSIONS[1]));
+        assert!(!pages.is_unreleased(VERSIONS[2]));
+    }
+
+    #[test]
+    fn test_max_page_depth() {
+        assert_eq!(parsed_pages().max_page_depth(), 2);
+
+        let pages = ParsedPages::new(vec![(VERSIONS[1], nested_pages())], VERSIONS[1]);
+        assert_eq!(pages.max_page_depth(), 3);
+    }
+
+    #[test]
+    fn test_version_links_order() {
+        let pages = parsed_pages();
+
+        let versions: Vec<_> = pages.version_map[VERSIONS[2]].version_links["introduction"]
+            .iter()
+            .map(|version_link| version_link.version)
+            .collect();
+        assert_eq!(versions, vec![VERSIONS[1], VERSIONS[2]]);
+    }
 }
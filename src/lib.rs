@@ -1,39 +1,98 @@
+mod builder;
 mod code_samples;
 mod guides;
 mod search;
+mod seo;
+mod site_config;
+mod site_pages;
+mod sitemap;
+mod social_card;
 mod template_util;
+mod text_search;
 
+use std::path::PathBuf;
 use std::sync::Arc;
 
 use askama::filters::{HtmlSafe, Safe};
 use async_trait::async_trait;
 use cot::error::NotFound;
-use cot::error::handler::RequestError;
+use cot::error::handler::{DynErrorPageHandler, RequestError};
 use cot::html::Html;
-use cot::http::header;
+use cot::http::{StatusCode, header};
+use cot::json::Json;
 use cot::project::App;
-use cot::request::extractors::{FromRequestHead, Path, StaticFiles};
+use cot::request::extractors::{FromRequestHead, Path, StaticFiles, UrlQuery};
 use cot::request::{RequestExt, RequestHead};
-use cot::response::{IntoResponse, Response};
+use cot::response::{IntoResponse, Redirect, Response, ResponseExt};
+use cot::router::path::ReverseParamMap;
 use cot::router::{Route, Router, Urls};
 use cot::static_files::StaticFile;
-use cot::{ProjectContext, Template, reverse_redirect, static_files};
+use cot::{Body, ProjectContext, Template, reverse_redirect, static_files};
 pub use cot_site_common;
 use cot_site_common::md_pages::{MdPage, MdPageLink, Section};
-use cot_site_common::{ALL_VERSIONS, LATEST_VERSION};
-pub use cot_site_macros::external_md_page as md_page;
-use cot_site_macros::md_page as internal_md_page;
+pub use cot_site_macros::{external_guide_manifest as guide_manifest, external_md_page as md_page};
+use serde::{Deserialize, Serialize};
 
-use crate::guides::{ParsedPages, get_categories, get_prev_next_link};
-use crate::search::{SEARCH_INDEX, SEARCH_INDEX_TIMEOUT, SearchIndex, build_search_index};
+pub use crate::builder::{CotSiteAppBuilder, GuideNavigation, TemplateOverrides};
+use crate::guides::{ParsedPages, VersionLink, get_category, get_prev_next_link};
+pub use crate::search::GenerateSearchIndexTask;
+use crate::search::{
+    AppSearchIndex, MAX_SEARCH_INDEX_FILE_DEPTH, PageType, SEARCH_INDEX_TIMEOUT, SearchIndex,
+};
+use crate::seo::{Breadcrumb, PageMeta};
+pub use crate::site_config::{SiteConfig, SocialLink};
+pub use crate::site_pages::StandalonePage;
+use crate::site_pages::{SitePage, site_page_url};
+use crate::sitemap::{render_robots_txt, render_sitemap, sitemap_entries};
+use crate::social_card::{SocialCard, SocialCards};
+use crate::text_search::{SearchResult, TextSearch};
 
-#[derive(Debug, Clone, FromRequestHead)]
+/// The context shared by all the templates of the site.
+#[derive(Debug, Clone)]
 pub struct BaseContext {
+    urls: Urls,
+    site: Arc<SiteConfig>,
+    static_files: StaticFiles,
+    route_name: RouteName,
+}
+
+/// The part of [`BaseContext`] extracted from the request; the rest of it
+/// comes from the app serving the request.
+#[derive(Debug, Clone, FromRequestHead)]
+struct RequestContext {
     urls: Urls,
     static_files: StaticFiles,
     route_name: RouteName,
 }
 
+impl RequestContext {
+    fn with_site(self, site: Arc<SiteConfig>) -> BaseContext {
+        BaseContext {
+            urls: self.urls,
+            site,
+            static_files: self.static_files,
+            route_name: self.route_name,
+        }
+    }
+}
+
+impl BaseContext {
+    /// Returns the URLs of the routes of the project.
+    pub fn urls(&self) -> &Urls {
+        &self.urls
+    }
+
+    /// Returns the configuration of the site.
+    pub fn site(&self) -> &SiteConfig {
+        &self.site
+    }
+
+    /// Returns the URLs of the static files of the project.
+    pub fn static_files(&self) -> &StaticFiles {
+        &self.static_files
+    }
+}
+
 #[derive(Debug, Clone)]
 struct RouteName(String);
 
@@ -50,17 +109,42 @@ impl PartialEq<str> for RouteName {
     }
 }
 
+/// The version of the guide that the reader was redirected from because it
+/// doesn't contain the requested page (see [`missing_page_redirect`]). It's
+/// validated against the versions of the guide when the page is rendered.
+#[derive(Debug, Clone)]
+struct MissingInVersion(Option<String>);
+
+const MISSING_IN_VERSION_PARAM: &str = "missing_in";
+
+impl FromRequestHead for MissingInVersion {
+    async fn from_request_head(head: &RequestHead) -> cot::Result<Self> {
+        let version = head
+            .uri
+            .query()
+            .into_iter()
+            .flat_map(|query| query.split('&'))
+            .filter_map(|pair| pair.split_once('='))
+            .find(|&(key, _value)| key == MISSING_IN_VERSION_PARAM)
+            .map(|(_key, value)| value.to_owned());
+        Ok(Self(version))
+    }
+}
+
 #[derive(Debug, Template)]
 #[template(path = "index.html")]
 struct IndexTemplate<'a> {
     base_context: &'a BaseContext,
 }
 
-async fn index(base_context: BaseContext) -> cot::Result<Html> {
-    let index_template = IndexTemplate {
-        base_context: &base_context,
+async fn index(base_context: BaseContext, templates: TemplateOverrides) -> cot::Result<Html> {
+    let rendered = match templates.index {
+        Some(render) => render(&base_context)?,
+        None => IndexTemplate {
+            base_context: &base_context,
+        }
+        .render()?,
     };
-    let rendered = index_template.render()?;
 
     Ok(Html::new(rendered))
 }
@@ -77,35 +161,51 @@ enum GuideCategoryItem {
     Page(MdPageLink),
     SubCategory {
         title: &'static str,
-        pages: Vec<MdPageLink>,
+        /// Unique ID of the subcategory, derived from its title and the titles
+        /// of its parent subcategories.
+        collapse_id: String,
+        items: Vec<GuideCategoryItem>,
     },
 }
 
 impl GuideCategoryItem {
     /// Takes a link and checks whether any of the pages in that subcategory
-    /// matches it. We call this inside the templates to decide whether a
-    /// subcategory accordion should start open or closed.
+    /// (including the nested subcategories) matches it. We call this inside
+    /// the templates to decide whether a subcategory accordion should start
+    /// open or closed.
     fn contains_active_page(&self, current_link: &str) -> bool {
         match self {
-            GuideCategoryItem::SubCategory { pages, .. } => {
-                pages.iter().any(|p| p.link == current_link)
-            }
+            GuideCategoryItem::SubCategory { items, .. } => items.iter().any(|item| match item {
+                GuideCategoryItem::Page(link) => link.link == current_link,
+                GuideCategoryItem::SubCategory { .. } => item.contains_active_page(current_link),
+            }),
             GuideCategoryItem::Page(_) => false,
         }
     }
     /// Returns a unique ID for the category which bootstrap uses to
     /// control the open/close behavior of the accordion
-    fn collapse_id(&self) -> String {
+    fn collapse_id(&self) -> &str {
         match self {
-            GuideCategoryItem::SubCategory { title, .. } => title.to_lowercase().replace(' ', "-"),
-            GuideCategoryItem::Page(_) => String::new(),
+            GuideCategoryItem::SubCategory { collapse_id, .. } => collapse_id,
+            GuideCategoryItem::Page(_) => "",
+        }
+    }
+
+    /// Returns the links to all the pages in the item, in order, including
+    /// the ones in the nested subcategories.
+    fn links(&self) -> Vec<&MdPageLink> {
+        match self {
+            GuideCategoryItem::Page(link) => vec![link],
+            GuideCategoryItem::SubCategory { items, .. } => {
+                items.iter().flat_map(GuideCategoryItem::links).collect()
+            }
         }
     }
 }
 
 /// Represents an item in a documentation guide. Each item can either be a
 /// single markdown page or a subcategory containing a collection of related
-/// pages.
+/// pages and (possibly) nested subcategories.
 pub enum GuideItem {
     /// A single markdown page to be rendered as part of the guide.
     ///
@@ -116,7 +216,8 @@ pub enum GuideItem {
     /// let page = GuideItem::Page(md_page!("guide", "introduction"));
     /// ```
     Page(MdPage),
-    /// A subcategory containing a collection of related pages.
+    /// A subcategory containing a collection of related pages, which can
+    /// contain subcategories itself.
     ///
     /// # Examples
     /// ```ignore
@@ -124,34 +225,87 @@ pub enum GuideItem {
     ///
     /// let subcategory = GuideItem::SubCategory{
     ///         title: "Database",
-    ///         pages: vec![
-    ///             md_page!("guide/databases/overview"),
-    ///             md_page!("guide/databases/queries"),
-    ///             md_page!("guide/databases/migrations"),
+    ///         items: vec![
+    ///             GuideItem::Page(md_page!("guide/databases/overview")),
+    ///             GuideItem::Page(md_page!("guide/databases/queries")),
+    ///             GuideItem::SubCategory {
+    ///                 title: "Backends",
+    ///                 items: vec![
+    ///                     GuideItem::Page(md_page!("guide/databases/backends/postgres")),
+    ///                 ],
+    ///             },
     ///         ]
     /// };
     /// ```
     SubCategory {
         title: &'static str,
-        pages: Vec<MdPage>,
+        items: Vec<GuideItem>,
     },
 }
 
+impl GuideItem {
+    /// Returns all the pages in the item, in order, including the ones in the
+    /// nested subcategories.
+    fn into_pages(self) -> Vec<MdPage> {
+        match self {
+            GuideItem::Page(page) => vec![page],
+            GuideItem::SubCategory { items, .. } => {
+                items.into_iter().flat_map(GuideItem::into_pages).collect()
+            }
+        }
+    }
+}
+
 #[derive(Debug, Template)]
 #[template(path = "guide.html")]
 struct GuideTemplate<'a> {
     link_categories: &'a [GuideLinkCategory],
     guide: &'a MdPage,
-    versions: &'static [&'static str],
+    /// The released versions, from the latest to the oldest.
+    released_versions: &'a [&'static str],
+    /// The unreleased versions, from the newest to the oldest.
+    unreleased_versions: &'a [&'static str],
+    /// Whether the page belongs to a version older than the latest one.
+    old_version: bool,
+    /// Whether the page belongs to a version newer than the latest one.
+    unreleased_version: bool,
+    version_links: &'a [VersionLink],
     version: &'a str,
     display_version: &'a str,
     canonical_link: &'a str,
+    page_meta: PageMeta,
     base_context: &'a BaseContext,
     search_index: SearchIndex,
+    missing_in_version: Option<&'static str>,
     prev: Option<&'a MdPageLink>,
     next: Option<&'a MdPageLink>,
 }
 
+fn render_guide_item(
+    item: &GuideCategoryItem,
+    guide: &MdPage,
+    urls: &Urls,
+    version: &str,
+) -> cot::Result<Safe<String>> {
+    #[derive(Debug, Template)]
+    #[template(path = "_guide_chapters_item.html")]
+    struct RenderableGuideItem<'a> {
+        item: &'a GuideCategoryItem,
+        guide: &'a MdPage,
+        urls: &'a Urls,
+        version: &'a str,
+    }
+
+    let rendered = RenderableGuideItem {
+        item,
+        guide,
+        urls,
+        version,
+    }
+    .render()?;
+    Ok(Safe(rendered))
+}
+
 fn render_section(section: &Section) -> Safe<String> {
     #[derive(Debug, Clone, Template)]
     #[template(path = "_md_page_toc_item.html")]
@@ -167,29 +321,86 @@ fn render_section(section: &Section) -> Safe<String> {
 
 const DEFAULT_GUIDE_PAGE: &str = "introduction";
 
-async fn guide(base_context: BaseContext) -> cot::Result<Response> {
-    reverse_redirect!(base_context.urls, "guide_version", version = "latest")
+async fn guide(urls: Urls) -> cot::Result<Response> {
+    reverse_redirect!(urls, "guide_version", version = "latest")
 }
 
 async fn guide_version(
     base_context: BaseContext,
     search_index: SearchIndex,
+    missing_in_version: MissingInVersion,
     Path(version): Path<String>,
     pages: Arc<ParsedPages>,
-) -> cot::Result<Html> {
+) -> cot::Result<Response> {
     page_response(
         base_context,
         search_index,
+        missing_in_version,
         &version,
         DEFAULT_GUIDE_PAGE,
         pages,
     )
 }
 
+/// The version and the link of a guide page, extracted from the path
+/// parameters of any of the guide page routes, regardless of how deeply the
+/// page is nested (see [`guide_page_route_url`]).
+#[derive(Debug, Clone)]
+struct GuidePagePath {
+    version: String,
+    page: String,
+}
+
+impl FromRequestHead for GuidePagePath {
+    async fn from_request_head(head: &RequestHead) -> cot::Result<Self> {
+        let params = head.path_params();
+        let version = params.get("version").ok_or_else(NotFound::new)?;
+        let page = (1..)
+            .map_while(|depth| params.get(&guide_page_section_param(depth)))
+            .chain(params.get("page"))
+            .collect::<Vec<_>>()
+            .join("/");
+
+        Ok(Self {
+            version: version.to_owned(),
+            page,
+        })
+    }
+}
+
+/// Returns the URL pattern of the route for the guide pages nested `depth`
+/// levels deep, e.g. `/guide/{version}/{section}/{section_2}/{page}/` for
+/// `depth == 3`.
+fn guide_page_route_url(depth: usize) -> String {
+    let sections: String = (1..depth)
+        .map(|depth| format!("{{{}}}/", guide_page_section_param(depth)))
+        .collect();
+    format!("/guide/{{version}}/{sections}{{page}}/")
+}
+
+/// Returns the name of the route for the guide pages nested `depth` levels
+/// deep (see [`guide_page_route_url`]).
+fn guide_page_route_name(depth: usize) -> String {
+    match depth {
+        1 => "guide_page".to_string(),
+        2 => "guide_section_page".to_string(),
+        _ => format!("guide_page_{depth}"),
+    }
+}
+
+fn guide_page_section_param(depth: usize) -> String {
+    if depth == 1 {
+        "section".to_string()
+    } else {
+        format!("section_{depth}")
+    }
+}
+
 async fn guide_page(
     base_context: BaseContext,
     search_index: SearchIndex,
-    Path((version, page)): Path<(String, String)>,
+    missing_in_version: MissingInVersion,
+    GuidePagePath { version, page }: GuidePagePath,
     pages: Arc<ParsedPages>,
 ) -> cot::Result<Response> {
     if page == DEFAULT_GUIDE_PAGE {
@@ -200,88 +411,369 @@ async fn guide_page(
         )?);
     }
 
-    page_response(base_context, search_index, &version, &page, pages).into_response()
-}
-
-async fn guide_section_page(
-    base_context: BaseContext,
-    search_index: SearchIndex,
-    Path((version, section, page)): Path<(String, String, String)>,
-    pages: Arc<ParsedPages>,
-) -> cot::Result<Response> {
-    let page = format!("{section}/{page}");
-    page_response(base_context, search_index, &version, &page, pages).into_response()
+    page_response(
+        base_context,
+        search_index,
+        missing_in_version,
+        &version,
+        &page,
+        pages,
+    )
 }
 
 fn page_response(
     base_context: BaseContext,
     search_index: SearchIndex,
+    MissingInVersion(missing_in_version): MissingInVersion,
     version: &str,
     page: &str,
-    pages: Arc<ParsedPages>,
-) -> cot::Result<Html> {
-    let file_version = if version == "latest" {
-        LATEST_VERSION
-    } else {
-        version
-    };
-    let pages = pages
+    all_pages: Arc<ParsedPages>,
+) -> cot::Result<Response> {
+    let file_version = all_pages
+        .resolve_version(version)
+        .ok_or_else(NotFound::new)?;
+    let pages = all_pages
         .version_map
         .get(file_version)
         .ok_or_else(NotFound::new)?;
-    let guide = pages.guide_map.get(page).ok_or_else(NotFound::new)?;
+    let Some(guide) = pages.guide_map.get(page) else {
+        return missing_page_redirect(&base_context.urls, &all_pages, version, page);
+    };
     let (prev, next) = get_prev_next_link(&pages.categories_links, page);
-    let canonical_link = canonical_link(&base_context.urls, file_version, page)
+    let version_links = pages
+        .version_links
+        .get(page)
+        .map(Vec::as_slice)
+        .unwrap_or_default();
+    let canonical_link = canonical_link(&base_context.site, &base_context.urls, file_version, page)
         .expect("Failed to create canonical link");
+    let page_meta = guide_page_meta(
+        &base_context.site,
+        &base_context.urls,
+        file_version,
+        guide,
+        &canonical_link,
+    )?;
 
     let guide_template = GuideTemplate {
         link_categories: &pages.categories_links,
         guide,
-        versions: ALL_VERSIONS,
+        released_versions: all_pages.released_versions(),
+        unreleased_versions: all_pages.unreleased_versions(),
+        old_version: file_version != all_pages.latest_version
+            && !all_pages.is_unreleased(file_version),
+        unreleased_version: all_pages.is_unreleased(file_version),
+        version_links,
         version,
         display_version: file_version,
         canonical_link: &canonical_link,
+        page_meta,
         base_context: &base_context,
         search_index,
+        missing_in_version: missing_in_version
+            .and_then(|missing_in_version| all_pages.resolve_version(&missing_in_version)),
         prev,
         next,
     };
 
     let rendered = guide_template.render()?;
-    Ok(Html::new(rendered))
+    Html::new(rendered).into_response()
 }
 
-fn canonical_link(urls: &Urls, version: &str, page: &str) -> cot::Result<String> {
-    const BASE_URL: &str = "https://cot.rs";
+/// Redirects to the page that was requested in a version that doesn't contain
+/// it. This is either the page that has the requested link as an alias in the
+/// same version, or the page in the nearest version that contains it, in
+/// which case the reader is notified that the page doesn't exist in the
+/// version they requested.
+fn missing_page_redirect(
+    urls: &Urls,
+    pages: &ParsedPages,
+    version: &str,
+    page: &str,
+) -> cot::Result<Response> {
+    let file_version = pages.resolve_version(version).ok_or_else(NotFound::new)?;
 
-    let path = if page == DEFAULT_GUIDE_PAGE {
-        cot::reverse!(urls, "guide_version", version = version)?
-    } else {
-        cot::reverse!(urls, "guide_page", version = version, page = page)?
-    };
+    if let Some(alias_target) = pages
+        .version_map
+        .get(file_version)
+        .and_then(|version_pages| version_pages.alias_map.get(page))
+    {
+        let url = guide_url(urls, version, alias_target)?;
+        return Redirect::new(url).into_response();
+    }
+
+    let (nearest_version, nearest_page) = pages
+        .find_nearest_version(file_version, page)
+        .ok_or_else(NotFound::new)?;
+    let url = guide_url(urls, nearest_version, nearest_page)?;
+    Redirect::new(format!("{url}?{MISSING_IN_VERSION_PARAM}={file_version}")).into_response()
+}
+
+/// Returns the URL of the guide page with the given link.
+///
+/// This is the only place that knows which route serves which page, so it
+/// should be used everywhere a guide URL is needed: in the templates, the
+/// search index, the canonical links, etc.
+pub(crate) fn guide_url(urls: &Urls, version: &str, page: &str) -> cot::Result<String> {
+    if page == DEFAULT_GUIDE_PAGE {
+        return Ok(cot::reverse!(urls, "guide_version", version = version)?);
+    }
+
+    let segments: Vec<&str> = page.split('/').collect();
+    match segments.as_slice() {
+        [page] => Ok(cot::reverse!(
+            urls,
+            "guide_page",
+            version = version,
+            page = page
+        )?),
+        [section, page] => Ok(cot::reverse!(
+            urls,
+            "guide_section_page",
+            version = version,
+            section = section,
+            page = page
+        )?),
+        [sections @ .., page] => {
+            let mut params = ReverseParamMap::new();
+            params.insert("version", version);
+            for (index, section) in sections.iter().enumerate() {
+                params.insert(guide_page_section_param(index + 1), section);
+            }
+            params.insert("page", page);
+            Ok(urls.router().reverse(
+                urls.app_name(),
+                &guide_page_route_name(segments.len()),
+                &params,
+            )?)
+        }
+        [] => unreachable!("splitting a string always returns at least one segment"),
+    }
+}
+
+/// Returns the URL of the page in another version of the guide, used by the
+/// version switcher.
+fn version_link_url(urls: &Urls, version_link: &VersionLink) -> cot::Result<String> {
+    guide_url(
+        urls,
+        version_link.version,
+        version_link.page.as_deref().unwrap_or(DEFAULT_GUIDE_PAGE),
+    )
+}
+
+fn canonical_link(
+    site: &SiteConfig,
+    urls: &Urls,
+    version: &str,
+    page: &str,
+) -> cot::Result<String> {
+    let path = guide_url(urls, version, page)?;
+
+    Ok(site.absolute_url(&path))
+}
+
+/// Returns the SEO metadata of a guide page. The breadcrumb trail goes through
+/// the introduction of the guide version the page belongs to.
+fn guide_page_meta(
+    site: &SiteConfig,
+    urls: &Urls,
+    version: &str,
+    page: &MdPage,
+    canonical_link: &str,
+) -> cot::Result<PageMeta> {
+    let mut breadcrumbs = vec![
+        index_breadcrumb(site, urls)?,
+        Breadcrumb {
+            name: format!("Guide ({version})"),
+            url: self::canonical_link(site, urls, version, DEFAULT_GUIDE_PAGE)?,
+        },
+    ];
+    if page.link != DEFAULT_GUIDE_PAGE {
+        breadcrumbs.push(Breadcrumb {
+            name: page.title.clone(),
+            url: canonical_link.to_owned(),
+        });
+    }
+
+    let social_card_url = social_card_url(urls, version, &page.link)?;
+
+    Ok(PageMeta::article(
+        site,
+        &page.title,
+        page_description(site, page),
+        canonical_link,
+        Some(version),
+        &breadcrumbs,
+    )
+    .with_image(site.absolute_url(&social_card_url))
+    .with_keywords(&page.keywords))
+}
+
+fn index_breadcrumb(site: &SiteConfig, urls: &Urls) -> cot::Result<Breadcrumb> {
+    Ok(Breadcrumb {
+        name: site.name.clone(),
+        url: site.absolute_url(&cot::reverse!(urls, "index")?),
+    })
+}
 
-    Ok(format!("{BASE_URL}{path}"))
+/// Returns the description of the page, falling back to the description of
+/// the whole site if the page doesn't have one.
+fn page_description<'a>(site: &'a SiteConfig, page: &'a MdPage) -> &'a str {
+    page.description.as_deref().unwrap_or(&site.description)
 }
 
 #[derive(Debug, Template)]
 #[template(path = "md_page.html")]
 struct MdPageTemplate<'a> {
     page: &'a MdPage,
+    page_meta: PageMeta,
     base_context: &'a BaseContext,
 }
 
-async fn faq(base_context: BaseContext) -> cot::Result<Html> {
-    let template = MdPageTemplate {
-        page: &internal_md_page!("", "faq"),
+/// The query parameters of the search page and the search API.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct SearchQuery {
+    q: String,
+    version: Option<String>,
+    /// The type of the pages to search (see [`PageType`]); all the pages are
+    /// searched if it's empty or invalid.
+    #[serde(rename = "type")]
+    page_type: String,
+}
+
+impl SearchQuery {
+    /// Returns the version to search in, which is the latest one by default.
+    fn version(&self) -> &str {
+        self.version.as_deref().unwrap_or("latest")
+    }
+
+    fn page_type(&self) -> Option<PageType> {
+        PageType::parse(&self.page_type)
+    }
+
+    fn search(
+        &self,
+        urls: &Urls,
+        pages: &ParsedPages,
+        text_search: &TextSearch,
+    ) -> cot::Result<Vec<SearchResult>> {
+        let version = self.version();
+        let file_version = pages.resolve_version(version).ok_or_else(NotFound::new)?;
+
+        text_search.search(urls, file_version, version, self.page_type(), &self.q)
+    }
+}
+
+#[derive(Debug, Template)]
+#[template(path = "search.html")]
+struct SearchTemplate<'a> {
+    base_context: &'a BaseContext,
+    query: &'a SearchQuery,
+    versions: &'a [&'static str],
+    results: Vec<SearchResult>,
+}
+
+async fn search(
+    base_context: BaseContext,
+    UrlQuery(query): UrlQuery<SearchQuery>,
+    pages: Arc<ParsedPages>,
+    text_search: Arc<TextSearch>,
+) -> cot::Result<Html> {
+    let results = query.search(&base_context.urls, &pages, &text_search)?;
+    let template = SearchTemplate {
         base_context: &base_context,
+        query: &query,
+        versions: &pages.versions,
+        results,
     };
 
     Ok(Html::new(template.render()?))
 }
 
-async fn licenses(base_context: BaseContext) -> cot::Result<Html> {
+/// The response of the search API.
+#[derive(Debug, Serialize)]
+struct SearchResponse {
+    query: String,
+    version: String,
+    results: Vec<SearchResult>,
+}
+
+async fn api_search(
+    urls: Urls,
+    UrlQuery(query): UrlQuery<SearchQuery>,
+    pages: Arc<ParsedPages>,
+    text_search: Arc<TextSearch>,
+) -> cot::Result<Json<SearchResponse>> {
+    let results = query.search(&urls, &pages, &text_search)?;
+
+    Ok(Json(SearchResponse {
+        version: query.version().to_owned(),
+        query: query.q,
+        results,
+    }))
+}
+
+async fn sitemap(
+    urls: Urls,
+    site: Arc<SiteConfig>,
+    pages: Arc<ParsedPages>,
+    site_pages: Arc<[SitePage]>,
+    other_versions_in_sitemap: bool,
+) -> cot::Result<Response> {
+    let entries = sitemap_entries(&site, &urls, &pages, &site_pages, other_versions_in_sitemap)?;
+
+    render_sitemap(&entries)
+        .into_bytes()
+        .with_content_type("application/xml; charset=utf-8")
+        .into_response()
+}
+
+async fn robots_txt(urls: Urls, site: Arc<SiteConfig>) -> cot::Result<Response> {
+    let disallowed_urls = [
+        cot::reverse!(urls, "search")?,
+        cot::reverse!(urls, "api_search")?,
+    ];
+
+    let sitemap_url = site.absolute_url(&cot::reverse!(urls, "sitemap")?);
+
+    render_robots_txt(&sitemap_url, &disallowed_urls)
+        .into_bytes()
+        .with_content_type("text/plain; charset=utf-8")
+        .into_response()
+}
+
+async fn standalone_page(
+    base_context: BaseContext,
+    page: &MdPage,
+    name: &str,
+    templates: TemplateOverrides,
+) -> cot::Result<Html> {
+    if let Some(render) = templates.standalone_page {
+        return Ok(Html::new(render(&base_context, page)?));
+    }
+
+    let site = &base_context.site;
+    let path = site_page_url(&base_context.urls, &format!("{name}/"))?;
+    let url = site.absolute_url(&path);
+    let breadcrumbs = [
+        index_breadcrumb(site, &base_context.urls)?,
+        Breadcrumb {
+            name: page.title.clone(),
+            url: url.clone(),
+        },
+    ];
     let template = MdPageTemplate {
-        page: &internal_md_page!("", "licenses"),
+        page,
+        page_meta: PageMeta::article(
+            site,
+            &page.title,
+            page_description(site, page),
+            &url,
+            None,
+            &breadcrumbs,
+        )
+        .with_keywords(&page.keywords),
         base_context: &base_context,
     };
 
@@ -289,47 +781,237 @@ async fn licenses(base_context: BaseContext) -> cot::Result<Html> {
 }
 
 // TODO: remove when Cot supports wildcard routes
-async fn serve_pagefind_2(
-    index: SearchIndex,
-    Path((dir, file)): Path<(String, String)>,
-) -> cot::Result<impl IntoResponse> {
-    serve_pagefind(index, Path(format!("{dir}/{file}"))).await
+/// A request for a file of the search index, extracted from any of the
+/// search index routes (see [`search_index_route_url`]).
+#[derive(Debug, Clone)]
+struct SearchIndexFileRequest {
+    hash: String,
+    path: String,
+    if_none_match: Option<String>,
+}
+
+impl FromRequestHead for SearchIndexFileRequest {
+    async fn from_request_head(head: &RequestHead) -> cot::Result<Self> {
+        let params = head.path_params();
+        let hash = params.get("hash").ok_or_else(NotFound::new)?;
+        let path = (1..)
+            .map_while(|depth| params.get(&search_index_dir_param(depth)))
+            .chain(params.get("file"))
+            .collect::<Vec<_>>()
+            .join("/");
+        let if_none_match = head
+            .headers
+            .get(header::IF_NONE_MATCH)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_owned);
+
+        Ok(Self {
+            hash: hash.to_owned(),
+            path,
+            if_none_match,
+        })
+    }
+}
+
+/// Returns the URL pattern of the route for the search index files with
+/// `depth` path segments, e.g. `/_pagefind/{hash}/{dir}/{file}` for
+/// `depth == 2`.
+fn search_index_route_url(depth: usize) -> String {
+    let dirs: String = (1..depth)
+        .map(|depth| format!("{{{}}}/", search_index_dir_param(depth)))
+        .collect();
+    format!("/_pagefind/{{hash}}/{dirs}{{file}}")
+}
+
+fn search_index_dir_param(depth: usize) -> String {
+    if depth == 1 {
+        "dir".to_string()
+    } else {
+        format!("dir_{depth}")
+    }
 }
 
+/// Serves a file of the search index.
+///
+/// The URLs of the files contain the hash of the whole index, so they can be
+/// cached indefinitely. Requests with an outdated hash (e.g. coming from a page
+/// rendered before a deploy) still get the current file, but it's only cached
+/// after revalidating it with the `ETag`.
 async fn serve_pagefind(
     index: SearchIndex,
-    Path(path): Path<String>,
-) -> cot::Result<impl IntoResponse> {
-    let content = index.get_file(&path).ok_or_else(NotFound::new)?;
-    let mime = mime_guess::from_path(&path).first_or_octet_stream();
+    request: SearchIndexFileRequest,
+) -> cot::Result<Response> {
+    let file = index.get_file(&request.path).ok_or_else(NotFound::new)?;
+    let cache_control = if request.hash == index.hash() {
+        format!(
+            "public, max-age={}, immutable",
+            SEARCH_INDEX_TIMEOUT.as_secs()
+        )
+    } else {
+        "no-cache".to_string()
+    };
+    let etag = header::HeaderValue::from_str(&file.etag).expect("failed to create ETag header");
+    let cache_control = header::HeaderValue::from_str(&cache_control)
+        .expect("failed to create cache control header");
 
-    Ok(content
-        .to_vec()
+    if request.if_none_match.as_deref() == Some(file.etag.as_str()) {
+        return Ok(Response::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .header(header::ETAG, etag)
+            .header(header::CACHE_CONTROL, cache_control)
+            .body(Body::empty())
+            .expect("failed to build a Not Modified response"));
+    }
+
+    let mime = mime_guess::from_path(&request.path).first_or_octet_stream();
+    file.contents
+        .clone()
         .with_content_type(mime.to_string())
+        .with_header(header::ETAG, etag)
+        .with_header(header::CACHE_CONTROL, cache_control)
+        .into_response()
+}
+
+/// The file name of the social card of a guide page, served next to the page
+/// itself, e.g. `/guide/latest/db-models/social-card.png`.
+const SOCIAL_CARD_FILE_NAME: &str = "social-card.png";
+const SOCIAL_CARD_CACHE_CONTROL: &str = "public, max-age=86400";
+
+fn social_card_url(urls: &Urls, version: &str, page: &str) -> cot::Result<String> {
+    Ok(format!(
+        "{}{SOCIAL_CARD_FILE_NAME}",
+        guide_url(urls, version, page)?
+    ))
+}
+
+/// Serves the image shown in the link previews of a guide page. The cards are
+/// rendered when they are first requested and kept in memory afterwards.
+async fn social_card(
+    GuidePagePath { version, page }: GuidePagePath,
+    site: Arc<SiteConfig>,
+    pages: Arc<ParsedPages>,
+    social_cards: Arc<SocialCards>,
+) -> cot::Result<Response> {
+    // the card of the introduction is served from the root of the version
+    let page = if page.is_empty() {
+        DEFAULT_GUIDE_PAGE
+    } else {
+        &page
+    };
+    let file_version = pages.resolve_version(&version).ok_or_else(NotFound::new)?;
+    let pages = pages
+        .version_map
+        .get(file_version)
+        .ok_or_else(NotFound::new)?;
+    let guide = pages.guide_map.get(page).ok_or_else(NotFound::new)?;
+
+    let card = SocialCard {
+        site_name: &site.name,
+        title: &guide.title,
+        category: get_category(&pages.categories_links, page),
+        version: file_version,
+    };
+    let png = social_cards.get_or_render(file_version, page, &card)?;
+
+    png.to_vec()
+        .with_content_type("image/png")
         .with_header(
             header::CACHE_CONTROL,
-            header::HeaderValue::from_str(&format!("max-age={}", SEARCH_INDEX_TIMEOUT.as_secs()))
-                .expect("failed to create cache control header"),
-        ))
+            header::HeaderValue::from_static(SOCIAL_CARD_CACHE_CONTROL),
+        )
+        .into_response()
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct CotSiteApp {
     pages: Arc<ParsedPages>,
+    /// The pages outside of the guide that are searchable and listed in the
+    /// sitemap, including the home page.
+    site_pages: Arc<[SitePage]>,
+    standalone_pages: Arc<[StandalonePage]>,
+    text_search: Arc<TextSearch>,
+    social_cards: Arc<SocialCards>,
+    search_index: Arc<AppSearchIndex>,
+    site_config: Arc<SiteConfig>,
+    templates: TemplateOverrides,
+    other_versions_in_sitemap: bool,
 }
 
 impl CotSiteApp {
-    /// Creates a new instance of [`CotSiteApp`].
+    /// Creates a new instance of [`CotSiteApp`] serving <https://cot.rs>
+    /// (see [`CotSiteAppBuilder::cot_rs`]).
     ///
     /// The `master_pages` parameter should contain a list of sections, where
     /// each section is a tuple containing the name of the section and list
-    /// of pages inside it.
-    pub fn new(master_pages: Vec<(&'static str, Vec<GuideItem>)>) -> Self {
-        let pages = get_categories(master_pages);
+    /// of pages inside it. It's usually generated from the `SUMMARY.md` file
+    /// of the guide with the [`guide_manifest!`] macro.
+    pub fn new(master_pages: GuideNavigation) -> Self {
+        CotSiteAppBuilder::cot_rs(master_pages).build()
+    }
 
-        Self {
-            pages: Arc::new(pages),
-        }
+    /// Returns a builder to configure the versions of the guide, the pages
+    /// and the metadata of the site.
+    pub fn builder() -> CotSiteAppBuilder {
+        CotSiteAppBuilder::default()
+    }
+
+    /// Sets the directory the search index is loaded from (`search-index` by
+    /// default). If it doesn't exist, the index is generated when the server
+    /// starts.
+    #[must_use]
+    pub fn with_search_index_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.search_index = Arc::new(AppSearchIndex::new(
+            dir.into(),
+            Arc::clone(&self.pages),
+            Arc::clone(&self.site_pages),
+        ));
+        self
+    }
+
+    /// Sets whether the guide pages of the versions other than the latest one
+    /// (the old versions and `master`) are included in `sitemap.xml`. They are
+    /// included by default, with a lower priority than the latest version.
+    #[must_use]
+    pub fn with_other_versions_in_sitemap(mut self, include: bool) -> Self {
+        self.other_versions_in_sitemap = include;
+        self
+    }
+
+    /// Returns the route of the social cards of the guide pages served by the
+    /// route with the given URL pattern.
+    fn social_card_route(&self, page_url: &str) -> Route {
+        let app = self.clone();
+        let handler = async move |path: GuidePagePath| {
+            social_card(
+                path,
+                Arc::clone(&app.site_config),
+                Arc::clone(&app.pages),
+                Arc::clone(&app.social_cards),
+            )
+            .await
+        };
+
+        Route::with_handler(&format!("{page_url}{SOCIAL_CARD_FILE_NAME}"), handler)
+    }
+
+    /// Returns the handler rendering the error pages of the site, to be
+    /// returned from
+    /// [`Project::error_handler`](cot::project::Project::error_handler).
+    pub fn error_handler(&self) -> DynErrorPageHandler {
+        let site = Arc::clone(&self.site_config);
+        DynErrorPageHandler::new(async move |context: RequestContext, error: RequestError| {
+            handle_error(context.with_site(Arc::clone(&site)), error).await
+        })
+    }
+
+    /// Returns the CLI command that generates the search index into the
+    /// directory it's loaded from.
+    pub fn generate_search_index_task(&self) -> GenerateSearchIndexTask {
+        GenerateSearchIndexTask::new(
+            Arc::clone(&self.pages),
+            Arc::clone(&self.site_pages),
+            self.search_index.dir().to_owned(),
+        )
     }
 }
 
@@ -340,63 +1022,163 @@ impl App for CotSiteApp {
     }
 
     fn router(&self) -> Router {
-        let pages_guide_version = self.pages.clone();
-        let pages_guide_page = self.pages.clone();
-        let pages_guide_section_page = self.pages.clone();
-
-        Router::with_urls([
-            Route::with_handler_and_name("/", index, "index"),
-            Route::with_handler_and_name("/faq/", faq, "faq"),
-            Route::with_handler_and_name("/licenses/", licenses, "licenses"),
+        // every handler gets its own copy of the app, which only holds `Arc`s
+        // of the shared state
+        let mut urls = vec![
+            Route::with_handler_and_name(
+                "/",
+                {
+                    let app = self.clone();
+                    async move |context: RequestContext| {
+                        index(
+                            context.with_site(Arc::clone(&app.site_config)),
+                            app.templates,
+                        )
+                        .await
+                    }
+                },
+                "index",
+            ),
             Route::with_handler_and_name("/guide/", guide, "guide"),
-            Route::with_handler_and_name("/_pagefind/{file}", serve_pagefind, "serve_pagefind"),
-            Route::with_handler("/_pagefind/{dir}/{file}", serve_pagefind_2),
             Route::with_handler_and_name(
-                "/guide/{version}/",
-                async move |base_context: BaseContext,
-                            search_index: SearchIndex,
-                            path: Path<String>| {
-                    guide_version(
-                        base_context,
-                        search_index,
-                        path,
-                        Arc::clone(&pages_guide_version),
-                    )
-                    .await
+                "/sitemap.xml",
+                {
+                    let app = self.clone();
+                    async move |urls: Urls| {
+                        sitemap(
+                            urls,
+                            Arc::clone(&app.site_config),
+                            Arc::clone(&app.pages),
+                            Arc::clone(&app.site_pages),
+                            app.other_versions_in_sitemap,
+                        )
+                        .await
+                    }
                 },
-                "guide_version",
+                "sitemap",
             ),
             Route::with_handler_and_name(
-                "/guide/{version}/{page}/",
-                async move |base_context: BaseContext,
-                            search_index: SearchIndex,
-                            path: Path<(String, String)>| {
-                    guide_page(
-                        base_context,
-                        search_index,
-                        path,
-                        Arc::clone(&pages_guide_page),
-                    )
-                    .await
+                "/robots.txt",
+                {
+                    let app = self.clone();
+                    async move |urls: Urls| robots_txt(urls, Arc::clone(&app.site_config)).await
                 },
-                "guide_page",
+                "robots_txt",
             ),
             Route::with_handler_and_name(
-                "/guide/{version}/{section}/{page}/",
-                async move |base_context: BaseContext,
-                            search_index: SearchIndex,
-                            path: Path<(String, String, String)>| {
-                    guide_section_page(
-                        base_context,
-                        search_index,
-                        path,
-                        Arc::clone(&pages_guide_section_page),
-                    )
-                    .await
+                "/search/",
+                {
+                    let app = self.clone();
+                    async move |context: RequestContext, query: UrlQuery<SearchQuery>| {
+                        search(
+                            context.with_site(Arc::clone(&app.site_config)),
+                            query,
+                            Arc::clone(&app.pages),
+                            Arc::clone(&app.text_search),
+                        )
+                        .await
+                    }
                 },
-                "guide_section_page",
+                "search",
             ),
-        ])
+            Route::with_handler_and_name(
+                "/api/search",
+                {
+                    let app = self.clone();
+                    async move |urls: Urls, query: UrlQuery<SearchQuery>| {
+                        api_search(
+                            urls,
+                            query,
+                            Arc::clone(&app.pages),
+                            Arc::clone(&app.text_search),
+                        )
+                        .await
+                    }
+                },
+                "api_search",
+            ),
+            Route::with_handler_and_name(
+                "/guide/{version}/",
+                {
+                    let app = self.clone();
+                    async move |context: RequestContext,
+                                missing_in_version: MissingInVersion,
+                                path: Path<String>| {
+                        let search_index = app.search_index.get(context.urls.clone()).await?;
+                        guide_version(
+                            context.with_site(Arc::clone(&app.site_config)),
+                            search_index,
+                            missing_in_version,
+                            path,
+                            Arc::clone(&app.pages),
+                        )
+                        .await
+                    }
+                },
+                "guide_version",
+            ),
+        ];
+
+        for (index, page) in self.standalone_pages.iter().enumerate() {
+            let app = self.clone();
+            let handler = async move |context: RequestContext| {
+                let page = &app.standalone_pages[index];
+                standalone_page(
+                    context.with_site(Arc::clone(&app.site_config)),
+                    &page.page,
+                    page.name,
+                    app.templates,
+                )
+                .await
+            };
+            urls.push(Route::with_handler_and_name(
+                &format!("/{}/", page.name),
+                handler,
+                page.name,
+            ));
+        }
+
+        // cot doesn't support wildcard routes, so there is a route for every
+        // nesting level of the search index files and the guide pages
+        for depth in 1..=MAX_SEARCH_INDEX_FILE_DEPTH {
+            let app = self.clone();
+            let handler = async move |urls: Urls, request: SearchIndexFileRequest| {
+                serve_pagefind(app.search_index.get(urls).await?, request).await
+            };
+            let url = search_index_route_url(depth);
+            urls.push(if depth == 1 {
+                Route::with_handler_and_name(&url, handler, "serve_pagefind")
+            } else {
+                Route::with_handler(&url, handler)
+            });
+        }
+        urls.push(self.social_card_route("/guide/{version}/"));
+        for depth in 1..=self.pages.max_page_depth() {
+            let app = self.clone();
+            let handler = async move |context: RequestContext,
+                                      missing_in_version: MissingInVersion,
+                                      path: GuidePagePath| {
+                let search_index = app.search_index.get(context.urls.clone()).await?;
+                guide_page(
+                    context.with_site(Arc::clone(&app.site_config)),
+                    search_index,
+                    missing_in_version,
+                    path,
+                    Arc::clone(&app.pages),
+                )
+                .await
+            };
+            let url = guide_page_route_url(depth);
+
+            urls.push(self.social_card_route(&url));
+            urls.push(Route::with_handler_and_name(
+                &url,
+                handler,
+                guide_page_route_name(depth),
+            ));
+        }
+
+        Router::with_urls(urls)
     }
 
     fn static_files(&self) -> Vec<StaticFile> {
@@ -405,6 +1187,7 @@ impl App for CotSiteApp {
             "static/css/main.css",
             "static/js/color-modes.js",
             "static/js/code-copy.js",
+            "static/js/code-tabs.js",
             "static/js/search.js",
             "static/images/cot-dark.svg",
             "static/images/favicon.svg",
@@ -418,17 +1201,12 @@ impl App for CotSiteApp {
     }
 
     async fn init(&self, context: &mut ProjectContext) -> cot::Result<()> {
-        let urls = Urls::from(context);
-        let search_index = build_search_index(urls, Arc::clone(&self.pages)).await;
-
-        SEARCH_INDEX
-            .set(search_index)
-            .expect("search index should be set once");
+        self.search_index.get(Urls::from(context)).await?;
         Ok(())
     }
 }
 
-pub async fn cot_site_handle_error(
+async fn handle_error(
     base_context: BaseContext,
     error: RequestError,
 ) -> cot::Result<impl IntoResponse> {
@@ -449,3 +1227,213 @@ pub async fn cot_site_handle_error(
 
     Ok(Html::new(rendered).with_status(status_code))
 }
+
+#[cfg(test)]
+mod tests {
+    use cot::config::ProjectConfig;
+    use cot::project::{
+        AppBuilder, MiddlewareContext, Project, RegisterAppsContext, RootHandler,
+        RootHandlerBuilder,
+    };
+    use cot::static_files::StaticFilesMiddleware;
+    use cot::test::{Client, TestRequestBuilder};
+
+    use super::*;
+
+    /// A project serving the app returned by the function.
+    struct TestProject(fn() -> CotSiteApp);
+
+    impl Project for TestProject {
+        fn config(&self, _config_name: &str) -> cot::Result<ProjectConfig> {
+            Ok(ProjectConfig::dev_default())
+        }
+
+        fn register_apps(&self, apps: &mut AppBuilder, _context: &RegisterAppsContext) {
+            apps.register_with_views(self.0(), "");
+        }
+
+        fn middlewares(
+            &self,
+            handler: RootHandlerBuilder,
+            context: &MiddlewareContext,
+        ) -> RootHandler {
+            handler
+                .middleware(StaticFilesMiddleware::from_context(context))
+                .build()
+        }
+    }
+
+    #[tokio::test]
+    async fn social_cards_resolve() {
+        let app = CotSiteApp::new(Vec::new());
+        let request = TestRequestBuilder::get("/").router(app.router()).build();
+        let urls = Urls::from_request(&request);
+        let latest_version = app.pages.latest_version;
+        let page = app.pages.version_map[latest_version]
+            .guide_map
+            .keys()
+            .map(String::as_str)
+            .find(|&page| page != DEFAULT_GUIDE_PAGE)
+            .unwrap();
+
+        let mut client = Client::new(TestProject(|| CotSiteApp::new(Vec::new()))).await;
+        for page in [DEFAULT_GUIDE_PAGE, page] {
+            let url = social_card_url(&urls, latest_version, page).unwrap();
+            let response = client.get(&url).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{url}");
+            assert_eq!(response.headers()[header::CONTENT_TYPE], "image/png");
+        }
+    }
+
+    /// Every URL added to the search index, including the site pages and the
+    /// guide pages of every version, resolves to a page.
+    #[tokio::test]
+    async fn indexed_urls_resolve() {
+        let app = CotSiteApp::new(Vec::new());
+        let request = TestRequestBuilder::get("/").router(app.router()).build();
+        let urls = Urls::from_request(&request);
+        let indexed_urls: Vec<String> = search::indexed_pages(&urls, &app.pages, &app.site_pages)
+            .unwrap()
+            .into_iter()
+            .map(|page| page.url)
+            .collect();
+        assert!(!indexed_urls.is_empty());
+
+        let mut client = Client::new(TestProject(|| CotSiteApp::new(Vec::new()))).await;
+        for url in indexed_urls {
+            let response = client.get(&url).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{url}");
+        }
+    }
+
+    fn page(link: &str) -> GuideItem {
+        GuideItem::Page(md_page(link))
+    }
+
+    fn md_page(link: &str) -> MdPage {
+        MdPage::new(link, link, "")
+    }
+
+    fn versioned_app() -> CotSiteApp {
+        CotSiteApp::builder()
+            .version(
+                "v0.4",
+                vec![(
+                    "Getting started",
+                    vec![page("introduction"), page("databases/overview")],
+                )],
+            )
+            .version(
+                "v0.3",
+                vec![("Getting started", vec![page("introduction")])],
+            )
+            .build()
+    }
+
+    #[tokio::test]
+    async fn guide_page_keywords() {
+        let mut client = Client::new(TestProject(|| {
+            CotSiteApp::builder()
+                .version(
+                    "v0.4",
+                    vec![(
+                        "Getting started",
+                        vec![
+                            page("introduction"),
+                            GuideItem::Page(MdPage {
+                                keywords: vec!["orm".to_string(), "database".to_string()],
+                                ..md_page("databases/overview")
+                            }),
+                        ],
+                    )],
+                )
+                .build()
+        }))
+        .await;
+
+        let response = client.get("/guide/v0.4/databases/overview/").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().into_bytes().await.unwrap();
+        let body = std::str::from_utf8(&body).unwrap();
+
+        assert!(body.contains("<meta name=\"keywords\" content=\"orm, database\">"));
+    }
+
+    #[tokio::test]
+    async fn missing_page_redirects_to_nearest_version() {
+        let mut client = Client::new(TestProject(versioned_app)).await;
+
+        let response = client.get("/guide/v0.3/databases/overview/").await.unwrap();
+
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            response.headers()[header::LOCATION],
+            "/guide/v0.4/databases/overview/?missing_in=v0.3"
+        );
+    }
+
+    #[test]
+    fn guide_url_nested_pages() {
+        let app = CotSiteApp::builder()
+            .version(
+                "v0.4",
+                vec![(
+                    "Getting started",
+                    vec![
+                        page("introduction"),
+                        page("databases/overview"),
+                        page("databases/queries/joins"),
+                    ],
+                )],
+            )
+            .build();
+        let request = TestRequestBuilder::get("/").router(app.router()).build();
+        let urls = Urls::from_request(&request);
+
+        assert_eq!(
+            guide_url(&urls, "v0.4", "databases/overview").unwrap(),
+            "/guide/v0.4/databases/overview/"
+        );
+        assert_eq!(
+            guide_url(&urls, "v0.4", "databases/queries/joins").unwrap(),
+            "/guide/v0.4/databases/queries/joins/"
+        );
+    }
+
+    fn app_with_standalone_pages(names: &[&'static str]) -> CotSiteApp {
+        names
+            .iter()
+            .fold(
+                CotSiteApp::builder().version(
+                    "v0.4",
+                    vec![("Getting started", vec![page("introduction")])],
+                ),
+                |builder, &name| builder.standalone_page(StandalonePage::new(name, md_page(name))),
+            )
+            .build()
+    }
+
+    #[tokio::test]
+    async fn standalone_page_loads_code_tabs() {
+        let mut client = Client::new(TestProject(|| app_with_standalone_pages(&["faq"]))).await;
+
+        let response = client.get("/faq/").await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().into_bytes().await.unwrap();
+        let body = std::str::from_utf8(&body).unwrap();
+
+        assert!(body.contains("/static/js/code-tabs."));
+    }
+
+    #[test]
+    #[should_panic(expected = "the standalone page name `search` is reserved")]
+    fn standalone_page_reserved_name() {
+        app_with_standalone_pages(&["faq", "search"]);
+    }
+
+    #[test]
+    #[should_panic(expected = "there should be only one standalone page named `faq`")]
+    fn standalone_page_duplicate_name() {
+        app_with_standalone_pages(&["faq", "licenses", "faq"]);
+    }
+}
@@ -1,27 +1,120 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use std::time::Duration;
 
-use cot::request::RequestHead;
-use cot::request::extractors::FromRequestHead;
+use async_trait::async_trait;
+use clap::{Arg, ArgMatches, Command, value_parser};
+use cot::cli::CliTask;
+use cot::project::{Bootstrapper, WithConfig};
 use cot::router::Urls;
+use cot_site_common::escape_html;
+use cot_site_common::md_pages::Section;
 use pagefind::api::PagefindIndex;
 use pagefind::options::PagefindServiceConfig;
-use tracing::info;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
 
+use crate::guide_url;
 use crate::guides::ParsedPages;
-
-pub static SEARCH_INDEX: tokio::sync::OnceCell<SearchIndex> = tokio::sync::OnceCell::const_new();
+use crate::site_pages::SitePage;
 
 pub const SEARCH_INDEX_TIMEOUT: Duration = Duration::from_secs(365 * 24 * 60 * 60); // 1 year
 
+/// The directory the search index is read from when it's not configured
+/// explicitly with [`CotSiteApp::with_search_index_dir`].
+///
+/// [`CotSiteApp::with_search_index_dir`]: crate::CotSiteApp::with_search_index_dir
+pub(crate) const DEFAULT_SEARCH_INDEX_DIR: &str = "search-index";
+
+/// The maximum number of path segments of the files in the search index (e.g.
+/// `fragment/en_1234.pf_fragment` has 2), as there is a separate route for
+/// every depth.
+pub(crate) const MAX_SEARCH_INDEX_FILE_DEPTH: usize = 4;
+
+// The Pagefind weights of the different parts of the pages; the weight of the
+// body text is 1.
+const TITLE_WEIGHT: &str = "7";
+const SECTION_HEADING_WEIGHT: &str = "5";
+const SUBSECTION_HEADING_WEIGHT: &str = "3";
+const INLINE_CODE_WEIGHT: &str = "2";
+
+/// The file every Pagefind index contains, used to tell a directory with a
+/// search index apart from any other directory.
+const PAGEFIND_ENTRY_FILE: &str = "pagefind.js";
+
+/// The code blocks longer than this are left out of the index, so that they
+/// don't fill the excerpts of the results.
+const MAX_INDEXED_CODE_BLOCK_LINES: usize = 10;
+
 #[derive(Debug, Clone)]
 pub struct SearchIndex {
-    files: Arc<HashMap<String, Vec<u8>>>,
+    files: Arc<HashMap<String, SearchIndexFile>>,
+    /// The hash of the contents of the whole index, used in the URLs of the
+    /// files so that they can be cached indefinitely.
+    hash: String,
+    /// The hash of the pages the index was generated from (see
+    /// [`pages_hash`]), if known. It's used to tell whether a saved index is
+    /// out of date.
+    pages_hash: Option<String>,
+}
+
+/// The file saved along with the index files, describing the index.
+const MANIFEST_FILE: &str = "cot-site-manifest.json";
+
+#[derive(Debug, Serialize, Deserialize)]
+struct SearchIndexManifest {
+    pages_hash: Option<String>,
+}
+
+/// A file of the search index, along with its strong `ETag`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SearchIndexFile {
+    pub contents: Vec<u8>,
+    pub etag: String,
 }
 
 impl SearchIndex {
-    pub async fn generate(urls: Urls, pages: Arc<ParsedPages>) -> cot::Result<Self> {
+    fn from_files(files: HashMap<String, Vec<u8>>) -> cot::Result<Self> {
+        if let Some(path) = files
+            .keys()
+            .find(|path| path.split('/').count() > MAX_SEARCH_INDEX_FILE_DEPTH)
+        {
+            return Err(cot::Error::internal(format!(
+                "Search index file {} is nested too deeply to be served",
+                path
+            )));
+        }
+
+        let mut paths: Vec<&String> = files.keys().collect();
+        paths.sort();
+        let mut hasher = blake3::Hasher::new();
+        for path in paths {
+            hasher.update(path.as_bytes());
+            hasher.update(&files[path]);
+        }
+        let hash = short_hash(hasher.finalize());
+
+        let files = files
+            .into_iter()
+            .map(|(path, contents)| {
+                let etag = format!("\"{}\"", short_hash(blake3::hash(&contents)));
+                (path, SearchIndexFile { contents, etag })
+            })
+            .collect();
+
+        Ok(Self {
+            files: Arc::new(files),
+            hash,
+            pages_hash: None,
+        })
+    }
+
+    pub async fn generate(
+        urls: Urls,
+        pages: Arc<ParsedPages>,
+        site_pages: Arc<[SitePage]>,
+    ) -> cot::Result<Self> {
         let options = PagefindServiceConfig::builder()
             .keep_index_url(true)
             .force_language("en".to_string())
@@ -30,21 +123,14 @@ impl SearchIndex {
         let mut indexer = PagefindIndex::new(Some(options))
             .map_err(|e| cot::Error::internal(format!("Failed to initialize Pagefind: {}", e)))?;
 
-        for (version, pages) in &pages.version_map {
-            for (page_id, page) in &pages.guide_map {
-                let url = cot::reverse!(urls, "guide_page", version = version, page = page_id)
-                    .expect("Failed to reverse URL for guide page");
-                let html = format!(
-                    r#"<html><body><article data-pagefind-body data-pagefind-filter="version:{}"><h1>{}</h1>{}</article></body></html>"#,
-                    version, page.title, page.content_html
-                );
-                indexer
-                    .add_html_file(None, Some(url), html)
-                    .await
-                    .map_err(|e| {
-                        cot::Error::internal(format!("Failed to add HTML to index: {}", e))
-                    })?;
-            }
+        let indexed_pages = indexed_pages(&urls, &pages, &site_pages)?;
+        let pages_hash = pages_hash(&indexed_pages);
+        for page in indexed_pages {
+            let html = page.index_html();
+            indexer
+                .add_html_file(None, Some(page.url), html)
+                .await
+                .map_err(|e| cot::Error::internal(format!("Failed to add HTML to index: {}", e)))?;
         }
 
         let files = indexer
@@ -58,55 +144,750 @@ impl SearchIndex {
         }
 
         Ok(Self {
-            files: Arc::new(files_map),
+            pages_hash: Some(pages_hash),
+            ..Self::from_files(files_map)?
         })
     }
 
-    pub fn get_file(&self, path: &str) -> Option<&[u8]> {
-        self.files.get(path).map(|v| v.as_slice())
+    /// Loads a search index previously written with [`SearchIndex::save`].
+    pub fn load(dir: &Path) -> cot::Result<Self> {
+        fn read_dir(
+            dir: &Path,
+            prefix: &str,
+            files: &mut HashMap<String, Vec<u8>>,
+        ) -> std::io::Result<()> {
+            for entry in std::fs::read_dir(dir)? {
+                let entry = entry?;
+                let name = format!("{prefix}{}", entry.file_name().to_string_lossy());
+                if entry.file_type()?.is_dir() {
+                    read_dir(&entry.path(), &format!("{name}/"), files)?;
+                } else {
+                    files.insert(name, std::fs::read(entry.path())?);
+                }
+            }
+            Ok(())
+        }
+
+        let mut files = HashMap::new();
+        let to_error = |e: std::io::Error| {
+            cot::Error::internal(format!(
+                "Failed to read the search index from {}: {}",
+                dir.display(),
+                e
+            ))
+        };
+        read_dir(dir, "", &mut files).map_err(to_error)?;
+        if !files.contains_key(PAGEFIND_ENTRY_FILE) {
+            return Err(cot::Error::internal(format!(
+                "{} does not contain a search index",
+                dir.display()
+            )));
+        }
+
+        let manifest = files.remove(MANIFEST_FILE).ok_or_else(|| {
+            cot::Error::internal(format!(
+                "{} does not contain the manifest of the search index",
+                dir.display()
+            ))
+        })?;
+        let manifest: SearchIndexManifest =
+            serde_json::from_slice(&manifest).map_err(|e| to_error(e.into()))?;
+
+        Ok(Self {
+            pages_hash: manifest.pages_hash,
+            ..Self::from_files(files)?
+        })
+    }
+
+    /// Writes the search index to the given directory, so that it can be
+    /// loaded with [`SearchIndex::load`].
+    ///
+    /// The index is written to a temporary directory next to `dir` first and
+    /// then moved in place of it. An existing directory is only replaced if
+    /// it contains a previously saved index, so that a mistyped path can't
+    /// wipe out anything else.
+    pub fn save(&self, dir: &Path) -> cot::Result<()> {
+        let to_error = |e: std::io::Error| {
+            cot::Error::internal(format!(
+                "Failed to write the search index to {}: {}",
+                dir.display(),
+                e
+            ))
+        };
+
+        let replaces_index = dir.exists();
+        if replaces_index && !dir.join(PAGEFIND_ENTRY_FILE).is_file() {
+            return Err(cot::Error::internal(format!(
+                "{} already exists and does not contain a search index; refusing to overwrite it",
+                dir.display()
+            )));
+        }
+        let name = dir.file_name().ok_or_else(|| {
+            cot::Error::internal(format!(
+                "{} is not a valid search index directory",
+                dir.display()
+            ))
+        })?;
+        let sibling = |suffix: &str| {
+            let mut sibling_name = std::ffi::OsString::from(".");
+            sibling_name.push(name);
+            sibling_name.push(format!(".{suffix}-{}", std::process::id()));
+            dir.with_file_name(sibling_name)
+        };
+
+        let new_dir = sibling("new");
+        if new_dir.exists() {
+            std::fs::remove_dir_all(&new_dir).map_err(to_error)?;
+        }
+        for (name, SearchIndexFile { contents, .. }) in self.files.iter() {
+            let path = new_dir.join(name);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent).map_err(to_error)?;
+            }
+            std::fs::write(path, contents).map_err(to_error)?;
+        }
+        let manifest = SearchIndexManifest {
+            pages_hash: self.pages_hash.clone(),
+        };
+        let manifest = serde_json::to_vec_pretty(&manifest).map_err(|e| to_error(e.into()))?;
+        std::fs::write(new_dir.join(MANIFEST_FILE), manifest).map_err(to_error)?;
+
+        if replaces_index {
+            let old_dir = sibling("old");
+            std::fs::rename(dir, &old_dir).map_err(to_error)?;
+            if let Err(e) = std::fs::rename(&new_dir, dir) {
+                // put the previous index back, so that the server still has one
+                std::fs::rename(&old_dir, dir).map_err(to_error)?;
+                return Err(to_error(e));
+            }
+            std::fs::remove_dir_all(&old_dir).map_err(to_error)?;
+        } else {
+            std::fs::rename(&new_dir, dir).map_err(to_error)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn get_file(&self, path: &str) -> Option<&SearchIndexFile> {
+        self.files.get(path)
+    }
+
+    /// Returns the hash of the whole index, which is a part of the URLs of
+    /// all the index files.
+    pub fn hash(&self) -> &str {
+        &self.hash
     }
 
     pub fn get_pagefind_url(&self, urls: &Urls) -> String {
-        let url = cot::reverse!(urls, "serve_pagefind", file = "pagefind.js")
-            .expect("Failed to reverse URL for pagefind.js");
-        let file = self
-            .get_file("pagefind.js")
-            .expect("pagefind.js should be in the search index");
-        let file_hash = hex::encode(&blake3::hash(file).as_slice()[0..6]);
-        format!("{}?v={}", url, file_hash)
+        cot::reverse!(
+            urls,
+            "serve_pagefind",
+            hash = &self.hash,
+            file = "pagefind.js"
+        )
+        .expect("Failed to reverse URL for pagefind.js")
+    }
+}
+
+fn short_hash(hash: blake3::Hash) -> String {
+    hex::encode(&hash.as_bytes()[0..6])
+}
+
+/// The type of a searchable page, used to scope the search results.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub(crate) enum PageType {
+    /// A page of the guide.
+    Guide,
+    /// A page outside of the guide (see [`searchable_site_pages`]).
+    ///
+    /// [`searchable_site_pages`]: crate::site_pages::searchable_site_pages
+    Site,
+}
+
+impl PageType {
+    /// Returns the name of the type, as used in the `type` Pagefind filter.
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            Self::Guide => "Guide",
+            Self::Site => "Site",
+        }
+    }
+
+    /// Parses the name of the type, case-insensitively.
+    pub(crate) fn parse(name: &str) -> Option<Self> {
+        [Self::Guide, Self::Site]
+            .into_iter()
+            .find(|page_type| page_type.as_str().eq_ignore_ascii_case(name))
+    }
+}
+
+/// A page to be added to the search index.
+#[derive(Debug)]
+pub(crate) struct IndexedPage<'a> {
+    pub(crate) url: String,
+    pub(crate) page_type: PageType,
+    /// The version of the guide the page belongs to, for guide pages.
+    pub(crate) version: Option<&'static str>,
+    /// The link of the page in the guide, for guide pages.
+    pub(crate) link: Option<&'a str>,
+    pub(crate) title: &'a str,
+    pub(crate) content_html: &'a str,
+    pub(crate) sections: &'a [Section],
+}
+
+impl IndexedPage<'_> {
+    /// Returns the HTML document added to the Pagefind index for the page.
+    fn index_html(&self) -> String {
+        let mut filters = format!("type:{}", self.page_type.as_str());
+        let mut meta = String::new();
+        if let Some((version, link)) = self.version.zip(self.link) {
+            // the metadata is used to group the results of all the versions
+            let (version, link) = (escape_html(version), escape_html(link));
+            filters.push_str(&format!(", version:{version}"));
+            meta = format!(r#" data-pagefind-meta="version:{version}, link:{link}""#);
+        }
+        format!(
+            r#"<html><body><article data-pagefind-body data-pagefind-filter="{}"{}><h1 data-pagefind-meta="title" data-pagefind-weight="{}">{}</h1>{}</article></body></html>"#,
+            filters,
+            meta,
+            TITLE_WEIGHT,
+            escape_html(self.title),
+            prepare_index_html(self.content_html, self.sections)
+        )
+    }
+}
+
+/// Returns the hash of everything that is added to the search index for the
+/// given pages, so that an index generated from different pages (or by a
+/// different version of the site) can be detected.
+fn pages_hash(pages: &[IndexedPage<'_>]) -> String {
+    let mut documents: Vec<_> = pages
+        .iter()
+        .map(|page| (page.url.as_str(), page.index_html()))
+        .collect();
+    documents.sort();
+
+    let mut hasher = blake3::Hasher::new();
+    for (url, html) in documents {
+        hasher.update(url.as_bytes());
+        hasher.update(&[0]);
+        hasher.update(html.as_bytes());
+        hasher.update(&[0]);
     }
+    short_hash(hasher.finalize())
 }
 
-impl FromRequestHead for SearchIndex {
-    async fn from_request_head(_head: &RequestHead) -> cot::Result<Self> {
-        let index = SEARCH_INDEX
-            .get()
-            .expect("search index should be initialized in init()");
-        Ok(index.clone())
+/// Returns all the pages that should be searchable, along with their URLs:
+/// the guide pages of all the versions, and the given site pages.
+pub(crate) fn indexed_pages<'a>(
+    urls: &Urls,
+    pages: &'a ParsedPages,
+    site_pages: &'a [SitePage],
+) -> cot::Result<Vec<IndexedPage<'a>>> {
+    let mut indexed_pages = Vec::new();
+
+    for (&version, pages) in &pages.version_map {
+        for (page_id, page) in &pages.guide_map {
+            indexed_pages.push(IndexedPage {
+                url: guide_url(urls, version, page_id)?,
+                page_type: PageType::Guide,
+                version: Some(version),
+                link: Some(page_id),
+                title: &page.title,
+                content_html: &page.content_html,
+                sections: &page.sections,
+            });
+        }
+    }
+    for page in site_pages {
+        indexed_pages.push(IndexedPage {
+            url: page.url(urls)?,
+            page_type: PageType::Site,
+            version: None,
+            link: None,
+            title: &page.title,
+            content_html: &page.content_html,
+            sections: &page.sections,
+        });
     }
+
+    Ok(indexed_pages)
 }
 
-pub async fn build_search_index(urls: Urls, pages: Arc<ParsedPages>) -> SearchIndex {
+/// Prepares the rendered HTML of a page for Pagefind: the section headings and
+/// the inline code get higher weights (so that looking up an identifier finds
+/// the page that documents it), and the long code blocks are marked with
+/// `data-pagefind-ignore`, so that they never end up in the excerpts.
+///
+/// Pagefind splits the results into sub-results at the headings with an `id`,
+/// so only the headings of the page's sections are weighted; that way, the
+/// sub-results always point to the anchors of the sections.
+fn prepare_index_html(html: &str, sections: &[Section]) -> String {
+    fn collect_anchors<'a>(sections: &'a [Section], anchors: &mut HashSet<&'a str>) {
+        for section in sections {
+            anchors.insert(&section.anchor);
+            collect_anchors(&section.children, anchors);
+        }
+    }
+
+    let mut anchors = HashSet::new();
+    collect_anchors(sections, &mut anchors);
+
+    let mut output = String::with_capacity(html.len());
+    let mut rest = html;
+    while let Some(index) = rest.find('<') {
+        output.push_str(&rest[..index]);
+        rest = &rest[index..];
+
+        if rest.starts_with("<pre") {
+            let end = rest
+                .find("</pre>")
+                .map_or(rest.len(), |end| end + "</pre>".len());
+            let (code_block, after) = rest.split_at(end);
+            if code_block.matches('\n').count() > MAX_INDEXED_CODE_BLOCK_LINES {
+                output.push_str("<pre data-pagefind-ignore");
+                output.push_str(&code_block["<pre".len()..]);
+            } else {
+                output.push_str(code_block);
+            }
+            rest = after;
+        } else if rest.starts_with("<code>") || rest.starts_with("<code ") {
+            output.push_str(&format!(
+                "<code data-pagefind-weight=\"{INLINE_CODE_WEIGHT}\""
+            ));
+            rest = &rest["<code".len()..];
+        } else if let Some(level) = section_heading_level(rest, &anchors) {
+            let weight = if level == '2' {
+                SECTION_HEADING_WEIGHT
+            } else {
+                SUBSECTION_HEADING_WEIGHT
+            };
+            output.push_str(&format!("<h{level} data-pagefind-weight=\"{weight}\""));
+            rest = &rest["<h1".len()..];
+        } else {
+            output.push('<');
+            rest = &rest[1..];
+        }
+    }
+    output.push_str(rest);
+
+    output
+}
+
+/// Returns the level of the heading at the start of `html`, if it's the
+/// heading of one of the sections with the given anchors.
+fn section_heading_level(html: &str, anchors: &HashSet<&str>) -> Option<char> {
+    let level = html.strip_prefix("<h")?.chars().next()?;
+    if !('2'..='6').contains(&level) {
+        return None;
+    }
+    let anchor = html["<h1".len()..]
+        .strip_prefix(" id=\"")?
+        .split_once('"')?
+        .0;
+
+    anchors.contains(anchor).then_some(level)
+}
+
+/// The search index of an app. It's loaded when the app is initialized or,
+/// if the server has been started without initializing the apps (e.g. in the
+/// tests), by the first request that needs it.
+#[derive(Debug)]
+pub(crate) struct AppSearchIndex {
+    index: tokio::sync::OnceCell<SearchIndex>,
+    dir: PathBuf,
+    pages: Arc<ParsedPages>,
+    site_pages: Arc<[SitePage]>,
+}
+
+impl AppSearchIndex {
+    pub(crate) fn new(dir: PathBuf, pages: Arc<ParsedPages>, site_pages: Arc<[SitePage]>) -> Self {
+        Self {
+            index: tokio::sync::OnceCell::new(),
+            dir,
+            pages,
+            site_pages,
+        }
+    }
+
+    /// The directory the index is loaded from.
+    pub(crate) fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub(crate) async fn get(&self, urls: Urls) -> cot::Result<SearchIndex> {
+        self.index
+            .get_or_try_init(|| {
+                load_search_index(
+                    &self.dir,
+                    urls,
+                    Arc::clone(&self.pages),
+                    Arc::clone(&self.site_pages),
+                )
+            })
+            .await
+            .cloned()
+    }
+}
+
+/// Generates the search index. This takes a while, as every page of every
+/// version has to be indexed, so the index is usually generated beforehand
+/// with [`GenerateSearchIndexTask`] and only loaded when the server starts.
+pub async fn build_search_index(
+    urls: Urls,
+    pages: Arc<ParsedPages>,
+    site_pages: Arc<[SitePage]>,
+) -> cot::Result<SearchIndex> {
     tokio::task::spawn_blocking(move || {
         // SearchIndex::generate is not Send due to the PagefindIndex it uses
         // internally, so we run it in a separate tokio runtime
         let rt = tokio::runtime::Builder::new_current_thread()
             .enable_all()
             .build()
-            .unwrap();
+            .map_err(|e| cot::Error::internal(format!("Failed to create a runtime: {}", e)))?;
         rt.block_on(async {
             info!("Generating search index...");
-            match SearchIndex::generate(urls, pages).await {
-                Ok(index) => {
-                    info!("Search index generated successfully");
-                    index
-                }
-                Err(e) => {
-                    panic!("Failed to generate search index: {}", e);
-                }
-            }
+            let index = SearchIndex::generate(urls, pages, site_pages).await?;
+            info!("Search index generated successfully");
+            Ok(index)
         })
     })
     .await
-    .expect("Failed to spawn blocking task for search index")
+    .map_err(|e| cot::Error::internal(format!("Failed to generate search index: {}", e)))?
+}
+
+/// Loads the search index from `dir`, where it has to be generated
+/// beforehand from the same pages with [`GenerateSearchIndexTask`].
+///
+/// In debug builds, a missing or out-of-date index is generated instead,
+/// which is convenient during development, but slows down the startup
+/// considerably.
+pub async fn load_search_index(
+    dir: &Path,
+    urls: Urls,
+    pages: Arc<ParsedPages>,
+    site_pages: Arc<[SitePage]>,
+) -> cot::Result<SearchIndex> {
+    let problem = if dir.exists() {
+        let index = SearchIndex::load(dir)?;
+        let pages_hash = pages_hash(&indexed_pages(&urls, &pages, &site_pages)?);
+        if index.pages_hash.as_deref() == Some(pages_hash.as_str()) {
+            info!("Search index loaded from {}", dir.display());
+            return Ok(index);
+        }
+        "is out of date"
+    } else {
+        "was not found"
+    };
+
+    if cfg!(debug_assertions) {
+        warn!(
+            "Search index in {} {problem}; generating it now. Run the `{}` command to generate it beforehand.",
+            dir.display(),
+            GenerateSearchIndexTask::NAME
+        );
+        return build_search_index(urls, pages, site_pages).await;
+    }
+    Err(cot::Error::internal(format!(
+        "Search index in {} {problem}; run the `{}` command to generate it",
+        dir.display(),
+        GenerateSearchIndexTask::NAME
+    )))
+}
+
+/// A CLI command that generates the search index and writes it to disk, so
+/// that the server doesn't have to do it on every start.
+///
+/// It should be registered in the project's [`Project::register_tasks`]
+/// using [`CotSiteApp::generate_search_index_task`].
+///
+/// [`Project::register_tasks`]: cot::project::Project::register_tasks
+/// [`CotSiteApp::generate_search_index_task`]: crate::CotSiteApp::generate_search_index_task
+#[derive(Debug)]
+pub struct GenerateSearchIndexTask {
+    pages: Arc<ParsedPages>,
+    site_pages: Arc<[SitePage]>,
+    default_dir: PathBuf,
+}
+
+impl GenerateSearchIndexTask {
+    const NAME: &'static str = "generate-search-index";
+
+    pub(crate) fn new(
+        pages: Arc<ParsedPages>,
+        site_pages: Arc<[SitePage]>,
+        default_dir: PathBuf,
+    ) -> Self {
+        Self {
+            pages,
+            site_pages,
+            default_dir,
+        }
+    }
+}
+
+#[async_trait(?Send)]
+impl CliTask for GenerateSearchIndexTask {
+    fn subcommand(&self) -> Command {
+        Command::new(Self::NAME)
+            .about("Generates the search index of the guide")
+            .arg(
+                Arg::new("dir")
+                    .help("The directory to write the search index to")
+                    .value_parser(value_parser!(PathBuf))
+                    .default_value(self.default_dir.clone().into_os_string()),
+            )
+    }
+
+    async fn execute(
+        &mut self,
+        matches: &ArgMatches,
+        bootstrapper: Bootstrapper<WithConfig>,
+    ) -> cot::Result<()> {
+        let dir = matches
+            .get_one::<PathBuf>("dir")
+            .expect("the argument has a default value");
+        let bootstrapper = bootstrapper.boot().await?;
+        let urls = Urls::from(bootstrapper.context());
+
+        let index =
+            build_search_index(urls, Arc::clone(&self.pages), Arc::clone(&self.site_pages)).await?;
+        index.save(dir)?;
+        info!("Search index written to {}", dir.display());
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn save_and_load() {
+        let dir =
+            std::env::temp_dir().join(format!("cot-site-search-index-{}", std::process::id()));
+        let index = SearchIndex::from_files(HashMap::from([
+            ("pagefind.js".to_string(), b"js".to_vec()),
+            (
+                "fragment/en_1234.pf_fragment".to_string(),
+                b"fragment".to_vec(),
+            ),
+        ]))
+        .unwrap();
+
+        index.save(&dir).unwrap();
+        let loaded = SearchIndex::load(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded.files, index.files);
+        assert_eq!(loaded.hash, index.hash);
+    }
+
+    fn test_dir(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("cot-site-{name}-{}", std::process::id()))
+    }
+
+    #[test]
+    fn save_and_load_pages_hash() {
+        let dir = test_dir("search-index-pages-hash");
+        let index = SearchIndex {
+            pages_hash: Some("0123456789ab".to_string()),
+            ..SearchIndex::from_files(HashMap::from([(
+                PAGEFIND_ENTRY_FILE.to_string(),
+                b"js".to_vec(),
+            )]))
+            .unwrap()
+        };
+
+        index.save(&dir).unwrap();
+        let loaded = SearchIndex::load(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded.pages_hash, index.pages_hash);
+        assert!(loaded.get_file(MANIFEST_FILE).is_none());
+        assert_eq!(loaded.hash(), index.hash());
+    }
+
+    #[test]
+    fn load_requires_manifest() {
+        let dir = test_dir("search-index-no-manifest");
+        SearchIndex::from_files(HashMap::from([(
+            PAGEFIND_ENTRY_FILE.to_string(),
+            b"js".to_vec(),
+        )]))
+        .unwrap()
+        .save(&dir)
+        .unwrap();
+        std::fs::remove_file(dir.join(MANIFEST_FILE)).unwrap();
+
+        let result = SearchIndex::load(&dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn pages_hash_depends_on_pages() {
+        let page = |url: &str, content_html: &'static str| IndexedPage {
+            url: url.to_string(),
+            page_type: PageType::Guide,
+            version: Some("v0.1"),
+            link: Some("introduction"),
+            title: "Introduction",
+            content_html,
+            sections: &[],
+        };
+
+        let hash = pages_hash(&[page("/a/", "<p>a</p>"), page("/b/", "<p>b</p>")]);
+        assert_eq!(
+            hash,
+            pages_hash(&[page("/b/", "<p>b</p>"), page("/a/", "<p>a</p>")])
+        );
+        assert_ne!(
+            hash,
+            pages_hash(&[page("/a/", "<p>a</p>"), page("/b/", "<p>changed</p>")])
+        );
+    }
+
+    #[test]
+    fn save_replaces_index() {
+        let dir = test_dir("search-index-replace");
+        let index = |contents: &[u8]| {
+            SearchIndex::from_files(HashMap::from([(
+                PAGEFIND_ENTRY_FILE.to_string(),
+                contents.to_vec(),
+            )]))
+            .unwrap()
+        };
+
+        index(b"old").save(&dir).unwrap();
+        index(b"new").save(&dir).unwrap();
+        let loaded = SearchIndex::load(&dir).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert_eq!(loaded.hash(), index(b"new").hash());
+    }
+
+    #[test]
+    fn save_refuses_to_overwrite_other_directory() {
+        let dir = test_dir("search-index-other");
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("Cargo.toml"), "[package]").unwrap();
+        let index = SearchIndex::from_files(HashMap::from([(
+            PAGEFIND_ENTRY_FILE.to_string(),
+            b"js".to_vec(),
+        )]))
+        .unwrap();
+
+        let result = index.save(&dir);
+        let kept = dir.join("Cargo.toml").exists();
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        assert!(result.is_err());
+        assert!(kept);
+    }
+
+    #[test]
+    fn hash_depends_on_contents() {
+        let index = |contents: &[u8]| {
+            SearchIndex::from_files(HashMap::from([(
+                "pagefind.js".to_string(),
+                contents.to_vec(),
+            )]))
+            .unwrap()
+        };
+
+        assert_eq!(index(b"js").hash(), index(b"js").hash());
+        assert_ne!(index(b"js").hash(), index(b"other").hash());
+        assert_ne!(
+            index(b"js").get_file("pagefind.js").unwrap().etag,
+            index(b"other").get_file("pagefind.js").unwrap().etag
+        );
+    }
+
+    fn section(anchor: &str, children: Vec<Section>) -> Section {
+        Section {
+            level: 2,
+            title: anchor.to_string(),
+            anchor: anchor.to_string(),
+            children,
+        }
+    }
+
+    #[test]
+    fn prepare_index_html_weights() {
+        let long_code_block = format!(
+            "<pre><code>{}</code></pre>",
+            "x\n".repeat(MAX_INDEXED_CODE_BLOCK_LINES + 1)
+        );
+        let html = format!(
+            "<h2 id=\"models\">Models</h2><p>Use <code>#[model]</code>.</p>\
+            <h3 id=\"fields\">Fields</h3><h4>Not a section</h4>\
+            <pre><code>short\n</code></pre>{long_code_block}"
+        );
+        let sections = [section("models", vec![section("fields", vec![])])];
+
+        assert_eq!(
+            prepare_index_html(&html, &sections),
+            format!(
+                "<h2 data-pagefind-weight=\"5\" id=\"models\">Models</h2>\
+                <p>Use <code data-pagefind-weight=\"2\">#[model]</code>.</p>\
+                <h3 data-pagefind-weight=\"3\" id=\"fields\">Fields</h3><h4>Not a section</h4>\
+                <pre><code>short\n</code></pre>\
+                <pre data-pagefind-ignore{}",
+                &long_code_block["<pre".len()..]
+            )
+        );
+    }
+
+    #[test]
+    fn prepare_index_html_code_blocks() {
+        let page = cot_site_macros::md_page!("v0.5", "admin-panel");
+        let code_blocks = page.content_html.matches("<pre").count();
+
+        let prepared = prepare_index_html(&page.content_html, &page.sections);
+        let long_code_blocks = prepared.matches("<pre data-pagefind-ignore").count();
+        assert!(long_code_blocks > 0);
+        assert!(long_code_blocks < code_blocks);
+        assert_eq!(prepared.matches("<pre").count(), code_blocks);
+    }
+
+    #[test]
+    fn index_html_escapes_text() {
+        let page = IndexedPage {
+            url: "/guide/v0.1/forms/".to_string(),
+            page_type: PageType::Guide,
+            version: Some("v0.1"),
+            link: Some("forms\"&<"),
+            title: "<Form> & \"validation\"",
+            content_html: "<p>Forms</p>",
+            sections: &[],
+        };
+
+        let html = page.index_html();
+        assert!(html.contains(r#"data-pagefind-meta="version:v0.1, link:forms&quot;&amp;&lt;""#));
+        assert!(html.contains(">&lt;Form&gt; &amp; &quot;validation&quot;</h1>"));
+    }
+
+    #[test]
+    fn parse_page_type() {
+        assert_eq!(PageType::parse("Guide"), Some(PageType::Guide));
+        assert_eq!(PageType::parse("site"), Some(PageType::Site));
+        assert_eq!(PageType::parse(""), None);
+        assert_eq!(PageType::parse("blog"), None);
+    }
+
+    #[test]
+    fn too_deeply_nested_file() {
+        let path = ["dir"; MAX_SEARCH_INDEX_FILE_DEPTH + 1].join("/");
+
+        assert!(SearchIndex::from_files(HashMap::from([(path, Vec::new())])).is_err());
+    }
+
+    #[test]
+    fn load_missing_index() {
+        let dir = std::env::temp_dir().join("cot-site-search-index-missing");
+
+        assert!(SearchIndex::load(&dir).is_err());
+    }
 }
@@ -0,0 +1,139 @@
+//! The metadata of the pages for the search engines and the link previews on
+//! social media.
+
+use serde_json::json;
+
+use crate::site_config::SiteConfig;
+
+/// The metadata of a page, rendered by `_page_meta.html` as the description,
+/// the Open Graph and Twitter tags, and the JSON-LD structured data.
+#[derive(Debug, Clone)]
+pub(crate) struct PageMeta {
+    pub(crate) title: String,
+    pub(crate) description: String,
+    /// The absolute URL of the page.
+    pub(crate) url: String,
+    /// The absolute URL of the image shown in the link previews; the logo is
+    /// shown if it's not set.
+    pub(crate) image: Option<String>,
+    /// The keywords from the front matter of the page.
+    pub(crate) keywords: Vec<String>,
+    /// The JSON-LD structured data, safe to embed in a `<script>` element.
+    pub(crate) json_ld: String,
+}
+
+/// A single item of the breadcrumb trail of a page.
+#[derive(Debug, Clone)]
+pub(crate) struct Breadcrumb {
+    pub(crate) name: String,
+    /// The absolute URL of the item.
+    pub(crate) url: String,
+}
+
+impl PageMeta {
+    /// Creates the metadata of a documentation page, described as a
+    /// `TechArticle` along with its `BreadcrumbList`. The last breadcrumb
+    /// should be the page itself.
+    pub(crate) fn article(
+        site: &SiteConfig,
+        title: &str,
+        description: &str,
+        url: &str,
+        version: Option<&str>,
+        breadcrumbs: &[Breadcrumb],
+    ) -> Self {
+        let mut article = json!({
+            "@type": "TechArticle",
+            "headline": title,
+            "description": description,
+            "url": url,
+            "inLanguage": "en",
+            "publisher": {
+                "@type": "Organization",
+                "name": site.name,
+                "url": site.base_url,
+            },
+        });
+        if let Some(version) = version {
+            article["version"] = json!(version);
+        }
+
+        let breadcrumb_list = json!({
+            "@type": "BreadcrumbList",
+            "itemListElement": breadcrumbs
+                .iter()
+                .enumerate()
+                .map(|(index, breadcrumb)| json!({
+                    "@type": "ListItem",
+                    "position": index + 1,
+                    "name": breadcrumb.name,
+                    "item": breadcrumb.url,
+                }))
+                .collect::<Vec<_>>(),
+        });
+
+        let json_ld = json!({
+            "@context": "https://schema.org",
+            "@graph": [article, breadcrumb_list],
+        });
+
+        Self {
+            title: title.to_string(),
+            description: description.to_string(),
+            url: url.to_string(),
+            image: None,
+            keywords: Vec::new(),
+            json_ld: escape_script(&json_ld.to_string()),
+        }
+    }
+
+    /// Sets the image shown in the link previews of the page.
+    pub(crate) fn with_image(mut self, image: String) -> Self {
+        self.image = Some(image);
+        self
+    }
+
+    /// Sets the keywords of the page.
+    pub(crate) fn with_keywords(mut self, keywords: &[String]) -> Self {
+        self.keywords = keywords.to_vec();
+        self
+    }
+}
+
+/// Makes sure the JSON can't close the `<script>` element it's embedded in;
+/// `<` can only appear inside the JSON strings, where it can be escaped.
+fn escape_script(json: &str) -> String {
+    json.replace('<', "\\u003c")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_article_json_ld() {
+        let meta = PageMeta::article(
+            &SiteConfig::default(),
+            "Models </script>",
+            "Defining models",
+            "https://cot.rs/guide/v0.5/db-models/",
+            Some("v0.5"),
+            &[
+                Breadcrumb {
+                    name: "Cot".to_string(),
+                    url: "https://cot.rs/".to_string(),
+                },
+                Breadcrumb {
+                    name: "Models".to_string(),
+                    url: "https://cot.rs/guide/v0.5/db-models/".to_string(),
+                },
+            ],
+        );
+
+        assert!(!meta.json_ld.contains("</script>"));
+        let json_ld: serde_json::Value = serde_json::from_str(&meta.json_ld).unwrap();
+        assert_eq!(json_ld["@graph"][0]["headline"], "Models </script>");
+        assert_eq!(json_ld["@graph"][0]["version"], "v0.5");
+        assert_eq!(json_ld["@graph"][1]["itemListElement"][1]["position"], 2);
+    }
+}
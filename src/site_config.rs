@@ -0,0 +1,137 @@
+//! The configuration of the website: the address it's deployed at and the
+//! metadata shown in the templates and the generated files.
+
+use serde::Deserialize;
+
+use crate::site_pages::SITE_DESCRIPTION;
+
+/// The address and the metadata of the website, used in the templates, the
+/// canonical links, `sitemap.xml`, `robots.txt` and the social media
+/// metadata.
+///
+/// The default configuration is the one of <https://cot.rs>. It can be
+/// deserialized (with the missing fields taken from the default), so that
+/// e.g. a staging deployment can override the base URL in its config file.
+///
+/// # Examples
+/// ```
+/// use cot_site::SiteConfig;
+///
+/// let config = SiteConfig {
+///     base_url: "https://staging.cot.rs".to_string(),
+///     ..SiteConfig::default()
+/// };
+/// assert_eq!(config.absolute_url("/faq/"), "https://staging.cot.rs/faq/");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct SiteConfig {
+    /// The URL the site is deployed at, e.g. `https://cot.rs`.
+    pub base_url: String,
+    /// The name of the site, shown in the page titles and the link previews.
+    pub name: String,
+    /// The description of the site, used for the pages that don't have
+    /// their own description.
+    pub description: String,
+    /// The URL of the repository of the project the site is about.
+    pub repository_url: String,
+    /// The links to the project on social media, shown in the footer.
+    pub social_links: Vec<SocialLink>,
+    /// The places to ask for help with the project besides the discussions
+    /// of the repository, shown in the footer.
+    pub help_links: Vec<SocialLink>,
+    /// The URL to sponsor the project at, shown in the footer if set.
+    pub sponsor_url: Option<String>,
+}
+
+/// A link to the project on another site, e.g. a social media site.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct SocialLink {
+    /// The name of the site, e.g. `Discord`.
+    pub name: String,
+    pub url: String,
+}
+
+impl SiteConfig {
+    /// Returns the absolute URL of the given path on the site.
+    pub fn absolute_url(&self, path: &str) -> String {
+        format!("{}{path}", self.base_url.trim_end_matches('/'))
+    }
+}
+
+impl Default for SiteConfig {
+    fn default() -> Self {
+        Self {
+            base_url: "https://cot.rs".to_string(),
+            name: "Cot".to_string(),
+            description: SITE_DESCRIPTION.to_string(),
+            repository_url: "https://github.com/cot-rs/cot".to_string(),
+            social_links: vec![
+                SocialLink {
+                    name: "GitHub".to_string(),
+                    url: "https://github.com/cot-rs/cot".to_string(),
+                },
+                SocialLink {
+                    name: "Discord".to_string(),
+                    url: "https://discord.cot.rs/".to_string(),
+                },
+                SocialLink {
+                    name: "Bluesky".to_string(),
+                    url: "https://bsky.app/profile/cot.rs".to_string(),
+                },
+            ],
+            help_links: vec![
+                SocialLink {
+                    name: "Stack Overflow".to_string(),
+                    url: "https://stackoverflow.com/tags/cot".to_string(),
+                },
+                SocialLink {
+                    name: "Discord".to_string(),
+                    url: "https://discord.cot.rs/".to_string(),
+                },
+            ],
+            sponsor_url: Some("https://github.com/sponsors/cot-rs/".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn absolute_url_trailing_slash() {
+        let config = SiteConfig {
+            base_url: "https://example.com/".to_string(),
+            ..SiteConfig::default()
+        };
+
+        assert_eq!(config.absolute_url("/guide/"), "https://example.com/guide/");
+    }
+
+    #[test]
+    fn deserialize_partial() {
+        let config: SiteConfig =
+            serde_json::from_str(r#"{"base_url": "https://staging.cot.rs"}"#).unwrap();
+
+        assert_eq!(config.base_url, "https://staging.cot.rs");
+        assert_eq!(config.name, SiteConfig::default().name);
+    }
+
+    #[test]
+    fn deserialize_footer_links() {
+        let config: SiteConfig = serde_json::from_str(
+            r#"{"help_links": [{"name": "Forum", "url": "https://forum.example.com"}], "sponsor_url": null}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.help_links,
+            [SocialLink {
+                name: "Forum".to_string(),
+                url: "https://forum.example.com".to_string(),
+            }]
+        );
+        assert_eq!(config.sponsor_url, None);
+    }
+}
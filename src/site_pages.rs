@@ -0,0 +1,146 @@
+//! The pages of the website outside of the guide.
+
+use cot::router::Urls;
+use cot_site_common::md_pages::{MdPage, Section};
+use cot_site_macros::md_page as internal_md_page;
+
+use crate::site_config::SiteConfig;
+
+/// The description of the website, used on the landing page.
+pub(crate) const SITE_DESCRIPTION: &str = "Cot is a powerful, type-safe, and fully featured Rust \
+    framework, delivering top-notch security and blazing speed. Cot empowers you to build \
+    production-ready web apps in record time — without compromising on performance or \
+    reliability.";
+
+/// The names the standalone pages can't have, as they are the names or the
+/// first path segments of the built-in routes. The names starting with
+/// `guide_page_` are reserved as well, for the routes of the guide pages
+/// nested more than two levels deep.
+pub(crate) const RESERVED_PAGE_NAMES: &[&str] = &[
+    "index",
+    "guide",
+    "guide_version",
+    "guide_page",
+    "guide_section_page",
+    "search",
+    "api",
+    "api_search",
+    "sitemap",
+    "sitemap.xml",
+    "robots_txt",
+    "robots.txt",
+    "serve_pagefind",
+    "_pagefind",
+    "static",
+];
+
+/// A markdown page outside of the guide, e.g. the FAQ, served at `/{name}/`
+/// in its own route named `name`. It can be found with the search and is
+/// listed in `sitemap.xml`.
+///
+/// # Examples
+/// The page is usually created from a Markdown file with the
+/// [`md_page!`](crate::md_page) macro.
+///
+/// ```no_run
+/// use cot_site::StandalonePage;
+/// use cot_site::cot_site_common::md_pages::MdPage;
+///
+/// let page = MdPage::new("faq", "FAQ", "<p>Questions and answers</p>");
+/// let faq = StandalonePage::new("faq", page).with_sitemap_priority(0.5);
+/// ```
+#[derive(Debug, Clone)]
+pub struct StandalonePage {
+    pub(crate) name: &'static str,
+    pub(crate) page: MdPage,
+    pub(crate) sitemap_priority: f32,
+}
+
+impl StandalonePage {
+    /// Creates a new standalone page with the given route name, which is also
+    /// its path.
+    pub fn new(name: &'static str, page: MdPage) -> Self {
+        Self {
+            name,
+            page,
+            sitemap_priority: DEFAULT_SITEMAP_PRIORITY,
+        }
+    }
+
+    /// Sets the priority of the page in `sitemap.xml`, relative to the other
+    /// pages of the site (between 0.0 and 1.0, 0.5 by default).
+    #[must_use]
+    pub fn with_sitemap_priority(mut self, priority: f32) -> Self {
+        self.sitemap_priority = priority;
+        self
+    }
+}
+
+const DEFAULT_SITEMAP_PRIORITY: f32 = 0.5;
+const INDEX_SITEMAP_PRIORITY: f32 = 1.0;
+
+/// A page outside of the guide that can be found with the search.
+#[derive(Debug, Clone)]
+pub(crate) struct SitePage {
+    /// The path of the page, relative to the home page (e.g. `faq/`).
+    pub(crate) path: String,
+    pub(crate) title: String,
+    pub(crate) content_html: String,
+    pub(crate) sections: Vec<Section>,
+    /// The priority of the page in `sitemap.xml` (between 0.0 and 1.0).
+    pub(crate) sitemap_priority: f32,
+}
+
+impl SitePage {
+    fn from_standalone_page(page: &StandalonePage) -> Self {
+        Self {
+            path: format!("{}/", page.name),
+            title: page.page.title.clone(),
+            content_html: page.page.content_html.clone(),
+            sections: page.page.sections.clone(),
+            sitemap_priority: page.sitemap_priority,
+        }
+    }
+
+    /// Returns the URL of the page.
+    pub(crate) fn url(&self, urls: &Urls) -> cot::Result<String> {
+        site_page_url(urls, &self.path)
+    }
+}
+
+/// Returns the URL of the site page with the given path (see
+/// [`SitePage::path`]).
+pub(crate) fn site_page_url(urls: &Urls, path: &str) -> cot::Result<String> {
+    Ok(format!("{}{path}", cot::reverse!(urls, "index")?))
+}
+
+pub(crate) fn faq_page() -> MdPage {
+    internal_md_page!("", "faq")
+}
+
+pub(crate) fn licenses_page() -> MdPage {
+    internal_md_page!("", "licenses")
+}
+
+/// Returns all the pages outside of the guide that should be searchable: the
+/// home page, described by the site config, and the standalone pages.
+///
+/// Both the Pagefind index and the server-side search are built from this
+/// list (as well as `sitemap.xml`), so a new page only has to be added here to
+/// become searchable.
+pub(crate) fn searchable_site_pages(
+    site: &SiteConfig,
+    standalone_pages: &[StandalonePage],
+) -> Vec<SitePage> {
+    let index = SitePage {
+        path: String::new(),
+        title: site.name.clone(),
+        content_html: format!("<p>{}</p>", site.description),
+        sections: Vec::new(),
+        sitemap_priority: INDEX_SITEMAP_PRIORITY,
+    };
+
+    std::iter::once(index)
+        .chain(standalone_pages.iter().map(SitePage::from_standalone_page))
+        .collect()
+}
@@ -0,0 +1,258 @@
+//! The `sitemap.xml` and `robots.txt` files for the search engines.
+
+use std::fmt::Write;
+
+use cot::router::Urls;
+use cot_site_common::escape_html;
+
+use crate::guide_url;
+use crate::guides::ParsedPages;
+use crate::site_config::SiteConfig;
+use crate::site_pages::SitePage;
+
+const LATEST_VERSION_PRIORITY: f32 = 0.8;
+const OTHER_VERSION_PRIORITY: f32 = 0.2;
+
+/// A single URL in the sitemap.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SitemapEntry {
+    /// The absolute URL of the page.
+    pub(crate) url: String,
+    /// The priority of the page, relative to the other pages of the site
+    /// (between 0.0 and 1.0).
+    pub(crate) priority: f32,
+}
+
+/// Returns the entries of the sitemap: the site pages, and the guide pages
+/// of all the versions. The pages of the latest version get a higher priority
+/// than the ones of the old and unreleased versions, which are only included
+/// if `include_other_versions` is set.
+pub(crate) fn sitemap_entries(
+    site: &SiteConfig,
+    urls: &Urls,
+    pages: &ParsedPages,
+    site_pages: &[SitePage],
+    include_other_versions: bool,
+) -> cot::Result<Vec<SitemapEntry>> {
+    let mut entries = site_pages
+        .iter()
+        .map(|page| {
+            Ok(SitemapEntry {
+                url: site.absolute_url(&page.url(urls)?),
+                priority: page.sitemap_priority,
+            })
+        })
+        .collect::<cot::Result<Vec<_>>>()?;
+
+    // in the order of the versions (newest first), so that the sitemap is stable
+    let latest_version = pages.latest_version;
+    let versions = pages
+        .versions
+        .iter()
+        .filter_map(|&version| Some((version, pages.version_map.get(version)?)));
+    for (version, pages) in versions {
+        let priority = if version == latest_version {
+            LATEST_VERSION_PRIORITY
+        } else if include_other_versions {
+            OTHER_VERSION_PRIORITY
+        } else {
+            continue;
+        };
+
+        let mut links: Vec<&String> = pages.guide_map.keys().collect();
+        links.sort();
+        for link in links {
+            entries.push(SitemapEntry {
+                url: site.absolute_url(&guide_url(urls, version, link)?),
+                priority,
+            });
+        }
+    }
+
+    Ok(entries)
+}
+
+/// Renders the sitemap in the [sitemaps.org](https://www.sitemaps.org/protocol.html)
+/// format.
+pub(crate) fn render_sitemap(entries: &[SitemapEntry]) -> String {
+    let mut sitemap = String::from(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+        <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n",
+    );
+    for entry in entries {
+        writeln!(
+            sitemap,
+            "  <url><loc>{}</loc><priority>{:.1}</priority></url>",
+            escape_html(&entry.url),
+            entry.priority
+        )
+        .expect("writing to a String cannot fail");
+    }
+    sitemap.push_str("</urlset>\n");
+
+    sitemap
+}
+
+/// Renders the `robots.txt` file, which points to the sitemap and keeps the
+/// crawlers away from the search results. The sitemap URL has to be absolute.
+pub(crate) fn render_robots_txt(sitemap_url: &str, disallowed_urls: &[String]) -> String {
+    let mut robots_txt = String::from("User-agent: *\n");
+    for url in disallowed_urls {
+        writeln!(robots_txt, "Disallow: {url}").expect("writing to a String cannot fail");
+    }
+    writeln!(robots_txt, "\nSitemap: {sitemap_url}").expect("writing to a String cannot fail");
+
+    robots_txt
+}
+
+#[cfg(test)]
+mod tests {
+    use cot::project::App;
+    use cot::test::TestRequestBuilder;
+    use cot_site_common::md_pages::MdPage;
+
+    use super::*;
+    use crate::{CotSiteApp, GuideItem, StandalonePage};
+
+    fn md_page(link: &str) -> MdPage {
+        MdPage::new(link, link, "")
+    }
+
+    fn app() -> CotSiteApp {
+        let navigation = || {
+            vec![(
+                "Getting started",
+                vec![
+                    GuideItem::Page(md_page("introduction")),
+                    GuideItem::Page(md_page("templates")),
+                    // the drafts are not listed
+                    GuideItem::Page(MdPage {
+                        draft: true,
+                        ..md_page("caching")
+                    }),
+                ],
+            )]
+        };
+        CotSiteApp::builder()
+            .version("master", navigation())
+            .version("v0.2", navigation())
+            .version("v0.1", navigation())
+            .latest_version("v0.2")
+            .standalone_page(StandalonePage::new("faq", md_page("faq")).with_sitemap_priority(0.5))
+            .standalone_page(StandalonePage::new(
+                "roadmap",
+                MdPage {
+                    draft: true,
+                    ..md_page("roadmap")
+                },
+            ))
+            .site_config(SiteConfig {
+                base_url: "https://docs.example.com/".to_string(),
+                ..SiteConfig::default()
+            })
+            .build()
+    }
+
+    fn entries(app: &CotSiteApp) -> Vec<(String, f32)> {
+        let request = TestRequestBuilder::get("/").router(app.router()).build();
+        let urls = Urls::from_request(&request);
+        sitemap_entries(
+            &app.site_config,
+            &urls,
+            &app.pages,
+            &app.site_pages,
+            app.other_versions_in_sitemap,
+        )
+        .unwrap()
+        .into_iter()
+        .map(|entry| (entry.url, entry.priority))
+        .collect()
+    }
+
+    #[test]
+    fn test_sitemap_entries() {
+        assert_eq!(
+            entries(&app()),
+            [
+                ("https://docs.example.com/".to_string(), 1.0),
+                ("https://docs.example.com/faq/".to_string(), 0.5),
+                (
+                    "https://docs.example.com/guide/master/".to_string(),
+                    OTHER_VERSION_PRIORITY
+                ),
+                (
+                    "https://docs.example.com/guide/master/templates/".to_string(),
+                    OTHER_VERSION_PRIORITY
+                ),
+                (
+                    "https://docs.example.com/guide/v0.2/".to_string(),
+                    LATEST_VERSION_PRIORITY
+                ),
+                (
+                    "https://docs.example.com/guide/v0.2/templates/".to_string(),
+                    LATEST_VERSION_PRIORITY
+                ),
+                (
+                    "https://docs.example.com/guide/v0.1/".to_string(),
+                    OTHER_VERSION_PRIORITY
+                ),
+                (
+                    "https://docs.example.com/guide/v0.1/templates/".to_string(),
+                    OTHER_VERSION_PRIORITY
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sitemap_entries_without_other_versions() {
+        let app = app().with_other_versions_in_sitemap(false);
+
+        assert_eq!(
+            entries(&app),
+            [
+                ("https://docs.example.com/".to_string(), 1.0),
+                ("https://docs.example.com/faq/".to_string(), 0.5),
+                (
+                    "https://docs.example.com/guide/v0.2/".to_string(),
+                    LATEST_VERSION_PRIORITY
+                ),
+                (
+                    "https://docs.example.com/guide/v0.2/templates/".to_string(),
+                    LATEST_VERSION_PRIORITY
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_render_sitemap() {
+        let sitemap = render_sitemap(&[
+            SitemapEntry {
+                url: "https://cot.rs/".to_string(),
+                priority: 1.0,
+            },
+            SitemapEntry {
+                url: "https://cot.rs/guide/v0.1/a&b/".to_string(),
+                priority: 0.2,
+            },
+        ]);
+
+        assert_eq!(
+            sitemap,
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+            <urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n\
+            \x20 <url><loc>https://cot.rs/</loc><priority>1.0</priority></url>\n\
+            \x20 <url><loc>https://cot.rs/guide/v0.1/a&amp;b/</loc><priority>0.2</priority></url>\n\
+            </urlset>\n"
+        );
+    }
+
+    #[test]
+    fn test_render_robots_txt() {
+        assert_eq!(
+            render_robots_txt("https://cot.rs/sitemap.xml", &["/search/".to_string()]),
+            "User-agent: *\nDisallow: /search/\n\nSitemap: https://cot.rs/sitemap.xml\n"
+        );
+    }
+}
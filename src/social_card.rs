@@ -0,0 +1,251 @@
+//! The images shown in the link previews of the guide pages on social media
+//! (`og:image`). They are rendered from an SVG template with the bundled
+//! fonts, so that they look the same regardless of the fonts installed on the
+//! server.
+
+use std::collections::HashMap;
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+
+use cot_site_common::escape_html;
+use resvg::tiny_skia::{Pixmap, Transform};
+use resvg::usvg::{Options, Tree, fontdb};
+
+/// The size of the card, as recommended for the Open Graph images.
+const CARD_WIDTH: u32 = 1200;
+const CARD_HEIGHT: u32 = 630;
+/// The maximum number of characters in a line of the title, so that it
+/// doesn't reach the logo.
+const MAX_TITLE_LINE_LENGTH: usize = 20;
+const MAX_TITLE_LINES: usize = 3;
+const LOGO_SIZE: f32 = 260.0;
+
+const FONT_FAMILY: &str = "DejaVu Sans";
+const FONTS: [&[u8]; 2] = [
+    include_bytes!("../fonts/DejaVuSans.ttf"),
+    include_bytes!("../fonts/DejaVuSans-Bold.ttf"),
+];
+const LOGO_SVG: &[u8] = include_bytes!("../static/static/images/cot-twocolor.svg");
+
+/// The contents of the social card of a guide page.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SocialCard<'a> {
+    pub(crate) site_name: &'a str,
+    pub(crate) title: &'a str,
+    /// The title of the category the page belongs to.
+    pub(crate) category: Option<&'a str>,
+    pub(crate) version: &'a str,
+}
+
+impl SocialCard<'_> {
+    /// Returns the SVG document of the card, without the logo, which is
+    /// rendered on top of it separately.
+    fn to_svg(self) -> String {
+        let mut svg = format!(
+            r##"<svg xmlns="http://www.w3.org/2000/svg" width="{CARD_WIDTH}" height="{CARD_HEIGHT}" viewBox="0 0 {CARD_WIDTH} {CARD_HEIGHT}" font-family="{FONT_FAMILY}">
+<defs><linearGradient id="background" x1="0" y1="0" x2="1" y2="1"><stop offset="0" stop-color="#1c2733"/><stop offset="1" stop-color="#33475d"/></linearGradient></defs>
+<rect width="{CARD_WIDTH}" height="{CARD_HEIGHT}" fill="url(#background)"/>
+<rect y="{}" width="{CARD_WIDTH}" height="12" fill="#6b839e"/>
+"##,
+            CARD_HEIGHT - 12
+        );
+
+        if let Some(category) = self.category {
+            writeln!(
+                svg,
+                r##"<text x="80" y="130" font-size="32" fill="#9fb3c8">{}</text>"##,
+                escape_html(category)
+            )
+            .expect("writing to a String cannot fail");
+        }
+
+        svg.push_str(r##"<text x="80" y="230" font-size="56" font-weight="bold" fill="#ffffff">"##);
+        for (index, line) in wrap_title(self.title).iter().enumerate() {
+            let dy = if index == 0 { 0 } else { 72 };
+            write!(
+                svg,
+                r#"<tspan x="80" dy="{dy}">{}</tspan>"#,
+                escape_html(line)
+            )
+            .expect("writing to a String cannot fail");
+        }
+        svg.push_str("</text>\n");
+
+        writeln!(
+            svg,
+            r##"<text x="80" y="550" font-size="36" fill="#ffffff"><tspan font-weight="bold">{}</tspan><tspan fill="#9fb3c8"> · Guide {}</tspan></text>"##,
+            escape_html(self.site_name),
+            escape_html(self.version)
+        )
+        .expect("writing to a String cannot fail");
+        svg.push_str("</svg>\n");
+
+        svg
+    }
+}
+
+/// Splits the title into lines short enough to fit on the card. If the title
+/// doesn't fit in [`MAX_TITLE_LINES`] lines, the last line ends with an
+/// ellipsis.
+fn wrap_title(title: &str) -> Vec<String> {
+    let mut lines: Vec<String> = Vec::new();
+    for word in title.split_whitespace() {
+        match lines.last_mut() {
+            Some(line)
+                if line.chars().count() + 1 + word.chars().count() <= MAX_TITLE_LINE_LENGTH =>
+            {
+                line.push(' ');
+                line.push_str(word);
+            }
+            _ => lines.push(word.to_owned()),
+        }
+    }
+
+    if lines.len() > MAX_TITLE_LINES {
+        lines.truncate(MAX_TITLE_LINES);
+        let last_line = lines.last_mut().expect("MAX_TITLE_LINES is not 0");
+        last_line.push('…');
+    }
+    lines
+}
+
+/// The rendered cards, by the version and the link of the page.
+type CardCache = HashMap<(String, String), Arc<[u8]>>;
+
+/// Renders the social cards into PNG images and keeps them in memory, as
+/// there is only one card for each guide page.
+#[derive(Debug)]
+pub(crate) struct SocialCards {
+    fontdb: Arc<fontdb::Database>,
+    logo: Tree,
+    cache: Mutex<CardCache>,
+}
+
+impl SocialCards {
+    pub(crate) fn new() -> Self {
+        let mut fontdb = fontdb::Database::new();
+        for font in FONTS {
+            fontdb.load_font_data(font.to_vec());
+        }
+        let logo =
+            Tree::from_data(LOGO_SVG, &Options::default()).expect("the logo should be a valid SVG");
+
+        Self {
+            fontdb: Arc::new(fontdb),
+            logo,
+            cache: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the PNG image of the card of the given page, rendering it if
+    /// it hasn't been requested before.
+    pub(crate) fn get_or_render(
+        &self,
+        version: &str,
+        page: &str,
+        card: &SocialCard<'_>,
+    ) -> cot::Result<Arc<[u8]>> {
+        let key = (version.to_owned(), page.to_owned());
+        if let Some(png) = self.cache.lock().expect("poisoned lock").get(&key) {
+            return Ok(Arc::clone(png));
+        }
+
+        // the lock is not held while rendering, as it takes a while; rendering
+        // the same card twice at the same time is harmless
+        let png: Arc<[u8]> = self.render(card)?.into();
+        self.cache
+            .lock()
+            .expect("poisoned lock")
+            .insert(key, Arc::clone(&png));
+        Ok(png)
+    }
+
+    fn render(&self, card: &SocialCard<'_>) -> cot::Result<Vec<u8>> {
+        let options = Options {
+            font_family: FONT_FAMILY.to_owned(),
+            fontdb: Arc::clone(&self.fontdb),
+            ..Options::default()
+        };
+        let tree = Tree::from_str(&card.to_svg(), &options)
+            .map_err(|e| cot::Error::internal(format!("Failed to parse the social card: {}", e)))?;
+
+        let mut pixmap = Pixmap::new(CARD_WIDTH, CARD_HEIGHT)
+            .ok_or_else(|| cot::Error::internal("Failed to allocate the social card"))?;
+        resvg::render(&tree, Transform::default(), &mut pixmap.as_mut());
+
+        let logo_scale = LOGO_SIZE / self.logo.size().width().max(self.logo.size().height());
+        let logo_transform = Transform::from_scale(logo_scale, logo_scale).post_translate(
+            CARD_WIDTH as f32 - LOGO_SIZE - 80.0,
+            (CARD_HEIGHT as f32 - LOGO_SIZE) / 2.0,
+        );
+        resvg::render(&self.logo, logo_transform, &mut pixmap.as_mut());
+
+        pixmap
+            .encode_png()
+            .map_err(|e| cot::Error::internal(format!("Failed to encode the social card: {}", e)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_short_title() {
+        assert_eq!(wrap_title("Introduction"), vec!["Introduction"]);
+    }
+
+    #[test]
+    fn wrap_long_title() {
+        assert_eq!(
+            wrap_title("Writing a custom database backend for the ORM"),
+            vec!["Writing a custom", "database backend for", "the ORM"]
+        );
+    }
+
+    #[test]
+    fn wrap_too_long_title() {
+        let lines = wrap_title(
+            "A title that is way too long to fit on the social card, even when split into many lines",
+        );
+
+        assert_eq!(lines.len(), MAX_TITLE_LINES);
+        assert!(lines.last().unwrap().ends_with('…'));
+    }
+
+    #[test]
+    fn card_svg_escapes_text() {
+        let card = SocialCard {
+            site_name: "Cot",
+            title: "Forms & <input>",
+            category: Some("Getting started"),
+            version: "v0.5",
+        };
+        let svg = card.to_svg();
+
+        assert!(svg.contains("Forms &amp; &lt;input&gt;"));
+        assert!(svg.contains("Getting started"));
+        assert!(svg.contains("Guide v0.5"));
+    }
+
+    #[test]
+    fn render_png() {
+        let cards = SocialCards::new();
+        let card = SocialCard {
+            site_name: "Cot",
+            title: "Introduction",
+            category: None,
+            version: "master",
+        };
+
+        let png = cards
+            .get_or_render("master", "introduction", &card)
+            .unwrap();
+
+        assert!(png.starts_with(b"\x89PNG"));
+        let cached = cards
+            .get_or_render("master", "introduction", &card)
+            .unwrap();
+        assert!(Arc::ptr_eq(&png, &cached));
+    }
+}
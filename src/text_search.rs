@@ -0,0 +1,488 @@
+//! A simple full-text search over the guide pages, done on the server side.
+//!
+//! It searches the same content that is indexed by Pagefind (see
+//! [`crate::search`]), but it doesn't need JavaScript, so it's used by the
+//! search results page and the JSON search API.
+
+use std::collections::HashMap;
+
+use cot::router::Urls;
+use cot_site_common::escape_html;
+use serde::Serialize;
+
+use crate::guide_url;
+use crate::guides::ParsedPages;
+use crate::search::PageType;
+use crate::site_pages::{SitePage, site_page_url};
+
+/// The maximum number of results returned for a query.
+pub(crate) const MAX_RESULTS: usize = 20;
+
+/// The number of words in the excerpts of the results.
+const EXCERPT_WORDS: usize = 30;
+
+/// The maximum length of a query, in characters; the rest of it is ignored.
+const MAX_QUERY_LENGTH: usize = 200;
+/// The maximum number of terms in a query; the rest of them are ignored.
+const MAX_QUERY_TERMS: usize = 10;
+
+const PAGE_TITLE_WEIGHT: usize = 10;
+const SECTION_TITLE_WEIGHT: usize = 5;
+
+#[derive(Debug)]
+pub(crate) struct TextSearch {
+    version_map: HashMap<&'static str, Vec<SearchDocument>>,
+    site_documents: Vec<SearchDocument>,
+}
+
+/// A searchable page, split into its sections.
+#[derive(Debug)]
+struct SearchDocument {
+    target: DocumentTarget,
+    title: String,
+    /// The words of the title, as returned by [`tokenize`].
+    title_words: Vec<String>,
+    sections: Vec<SearchSection>,
+}
+
+#[derive(Debug)]
+enum DocumentTarget {
+    /// A guide page with the given link.
+    Guide(String),
+    /// A site page with the given path (see [`SitePage::path`]).
+    Site(String),
+}
+
+impl DocumentTarget {
+    fn page_type(&self) -> PageType {
+        match self {
+            Self::Guide(_) => PageType::Guide,
+            Self::Site(_) => PageType::Site,
+        }
+    }
+
+    fn url(&self, urls: &Urls, version: &str) -> cot::Result<String> {
+        match self {
+            Self::Guide(link) => guide_url(urls, version, link),
+            Self::Site(path) => site_page_url(urls, path),
+        }
+    }
+}
+
+/// A part of a page starting at a heading (or the start of the page, in which
+/// case there is no anchor), converted to plain text.
+#[derive(Debug)]
+struct SearchSection {
+    title: Option<String>,
+    anchor: Option<String>,
+    text: String,
+    /// The words of the title and the text, as returned by [`tokenize`], so
+    /// that the pages are only tokenized once instead of for every query.
+    title_words: Vec<String>,
+    text_words: Vec<String>,
+}
+
+/// A single search result.
+#[derive(Debug, Clone, Serialize)]
+pub(crate) struct SearchResult {
+    pub(crate) title: String,
+    #[serde(rename = "type")]
+    pub(crate) page_type: PageType,
+    /// The title of the section that matched the query best, if it's not the
+    /// beginning of the page.
+    pub(crate) section: Option<String>,
+    /// The URL of the page, including the anchor of the section.
+    pub(crate) url: String,
+    /// A fragment of the matching section, HTML-escaped, with the query terms
+    /// wrapped in `<mark>` tags.
+    pub(crate) excerpt: String,
+}
+
+impl TextSearch {
+    pub(crate) fn new(pages: &ParsedPages, site_pages: &[SitePage]) -> Self {
+        let version_map = pages
+            .version_map
+            .iter()
+            .map(|(&version, pages)| {
+                let documents = pages
+                    .guide_map
+                    .iter()
+                    .map(|(link, page)| {
+                        SearchDocument::new(
+                            DocumentTarget::Guide(link.clone()),
+                            &page.title,
+                            &page.content_html,
+                        )
+                    })
+                    .collect();
+                (version, documents)
+            })
+            .collect();
+
+        let site_documents = site_pages
+            .iter()
+            .map(|page| {
+                SearchDocument::new(
+                    DocumentTarget::Site(page.path.clone()),
+                    &page.title,
+                    &page.content_html,
+                )
+            })
+            .collect();
+
+        Self {
+            version_map,
+            site_documents,
+        }
+    }
+
+    /// Searches the pages of the given version of the guide and the site
+    /// pages, or only the pages of the given type. Only the pages that contain
+    /// all the terms of the query are returned, sorted by relevance.
+    ///
+    /// The URLs of the results point to `url_version`, which might be an
+    /// alias of `version` (e.g. `latest`).
+    pub(crate) fn search(
+        &self,
+        urls: &Urls,
+        version: &str,
+        url_version: &str,
+        page_type: Option<PageType>,
+        query: &str,
+    ) -> cot::Result<Vec<SearchResult>> {
+        let terms = query_terms(query);
+        if terms.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let guide_documents = self.version_map.get(version).map(Vec::as_slice);
+        let mut matches: Vec<(usize, &SearchDocument, &SearchSection)> = guide_documents
+            .unwrap_or_default()
+            .iter()
+            .chain(&self.site_documents)
+            .filter(|document| {
+                page_type.is_none_or(|page_type| document.target.page_type() == page_type)
+            })
+            .filter_map(|document| {
+                let (score, section) = document.score(&terms)?;
+                Some((score, document, section))
+            })
+            .collect();
+        matches.sort_by(|(a_score, a, _), (b_score, b, _)| {
+            b_score.cmp(a_score).then_with(|| a.title.cmp(&b.title))
+        });
+
+        matches
+            .into_iter()
+            .take(MAX_RESULTS)
+            .map(|(_score, document, section)| {
+                let mut url = document.target.url(urls, url_version)?;
+                if let Some(anchor) = &section.anchor {
+                    url.push('#');
+                    url.push_str(anchor);
+                }
+
+                Ok(SearchResult {
+                    title: document.title.clone(),
+                    page_type: document.target.page_type(),
+                    section: section.title.clone(),
+                    url,
+                    excerpt: excerpt(&section.text, &terms),
+                })
+            })
+            .collect()
+    }
+}
+
+impl SearchDocument {
+    fn new(target: DocumentTarget, title: &str, content_html: &str) -> Self {
+        Self {
+            target,
+            title: title.to_string(),
+            title_words: tokenize(title),
+            sections: split_sections(content_html),
+        }
+    }
+
+    /// Returns the score of the page for the given terms, along with the
+    /// section that matches them best, or `None` if the page doesn't contain
+    /// all the terms.
+    fn score(&self, terms: &[String]) -> Option<(usize, &SearchSection)> {
+        let section_scores: Vec<(usize, &SearchSection)> = self
+            .sections
+            .iter()
+            .map(|section| {
+                let score = count_matches(&section.title_words, terms) * SECTION_TITLE_WEIGHT
+                    + count_matches(&section.text_words, terms);
+                (score, section)
+            })
+            .collect();
+
+        let all_terms_found = terms.iter().all(|term| {
+            contains_term(&self.title_words, term)
+                || self.sections.iter().any(|section| {
+                    contains_term(&section.title_words, term)
+                        || contains_term(&section.text_words, term)
+                })
+        });
+        if !all_terms_found {
+            return None;
+        }
+
+        let (best_score, best_section) = section_scores
+            .iter()
+            .copied()
+            .max_by_key(|&(score, section)| (score, std::cmp::Reverse(section.anchor.is_some())))?;
+        let score = count_matches(&self.title_words, terms) * PAGE_TITLE_WEIGHT
+            + section_scores.iter().map(|(score, _)| score).sum::<usize>();
+        // prefer the beginning of the page if no section matches particularly well
+        let section = if best_score == 0 {
+            &self.sections[0]
+        } else {
+            best_section
+        };
+
+        Some((score, section))
+    }
+}
+
+impl SearchSection {
+    fn new(title: Option<String>, anchor: Option<String>, text: String) -> Self {
+        Self {
+            title_words: title.as_deref().map(tokenize).unwrap_or_default(),
+            text_words: tokenize(&text),
+            title,
+            anchor,
+            text,
+        }
+    }
+}
+
+fn contains_term(words: &[String], term: &str) -> bool {
+    words.iter().any(|word| word.starts_with(term))
+}
+
+/// Counts the words that match any of the terms; the terms match the words
+/// they are a prefix of, so that e.g. `migrat` matches `migrations`.
+fn count_matches(words: &[String], terms: &[String]) -> usize {
+    words
+        .iter()
+        .filter(|word| terms.iter().any(|term| word.starts_with(term.as_str())))
+        .count()
+}
+
+/// Returns the terms of the query; only its first [`MAX_QUERY_LENGTH`]
+/// characters and [`MAX_QUERY_TERMS`] terms are used, so that long queries
+/// can't make the search slow.
+fn query_terms(query: &str) -> Vec<String> {
+    let query = query
+        .char_indices()
+        .nth(MAX_QUERY_LENGTH)
+        .map_or(query, |(end, _)| &query[..end]);
+    let mut terms = tokenize(query);
+    terms.truncate(MAX_QUERY_TERMS);
+    terms
+}
+
+fn tokenize(text: &str) -> Vec<String> {
+    text.split(|c: char| !c.is_alphanumeric() && c != '_')
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect()
+}
+
+/// Returns around [`EXCERPT_WORDS`] words of the text, starting a few words
+/// before the first match, with the matching words highlighted.
+fn excerpt(text: &str, terms: &[String]) -> String {
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let is_match = |word: &str| {
+        tokenize(word)
+            .iter()
+            .any(|token| terms.iter().any(|term| token.starts_with(term.as_str())))
+    };
+
+    let first_match = words.iter().position(|word| is_match(word)).unwrap_or(0);
+    let start = first_match
+        .saturating_sub(EXCERPT_WORDS / 4)
+        .min(words.len().saturating_sub(EXCERPT_WORDS));
+    let end = (start + EXCERPT_WORDS).min(words.len());
+
+    let mut excerpt = String::new();
+    if start > 0 {
+        excerpt.push_str("… ");
+    }
+    for (index, word) in words[start..end].iter().enumerate() {
+        if index > 0 {
+            excerpt.push(' ');
+        }
+        let escaped = escape_html(word);
+        if is_match(word) {
+            excerpt.push_str("<mark>");
+            excerpt.push_str(&escaped);
+            excerpt.push_str("</mark>");
+        } else {
+            excerpt.push_str(&escaped);
+        }
+    }
+    if end < words.len() {
+        excerpt.push_str(" …");
+    }
+
+    excerpt
+}
+
+/// Splits the rendered HTML of a page into sections at the headings with an
+/// `id` (which are the ones rendered by `MdPageHeadingAdapter`).
+fn split_sections(html: &str) -> Vec<SearchSection> {
+    let mut sections = Vec::new();
+    let mut title = None;
+    let mut anchor = None;
+    let mut rest = html;
+
+    while let Some(start) = find_heading(rest) {
+        let heading = &rest[start..];
+        let heading_anchor = heading
+            .split_once("id=\"")
+            .and_then(|(_, rest)| rest.split_once('"'))
+            .map(|(anchor, _)| anchor.to_string());
+        let level = &heading[2..3];
+        let closing_tag = format!("</h{level}>");
+        let (heading_html, after) = heading.split_once(&closing_tag).unwrap_or((heading, ""));
+
+        sections.push(SearchSection::new(
+            title.replace(html_to_text(heading_html)),
+            std::mem::replace(&mut anchor, heading_anchor),
+            html_to_text(&rest[..start]),
+        ));
+        rest = after;
+    }
+    sections.push(SearchSection::new(title, anchor, html_to_text(rest)));
+
+    sections
+}
+
+fn find_heading(html: &str) -> Option<usize> {
+    html.match_indices("<h")
+        .map(|(index, _)| index)
+        .find(|&index| {
+            let tag = &html[index..];
+            tag.len() > 3
+                && matches!(tag.as_bytes()[2], b'2'..=b'6')
+                && tag[3..].starts_with(" id=\"")
+        })
+}
+
+/// Strips the tags from the HTML and decodes the basic entities.
+fn html_to_text(html: &str) -> String {
+    let mut text = String::with_capacity(html.len());
+    let mut in_tag = false;
+
+    for c in html.chars() {
+        match c {
+            '<' => {
+                in_tag = true;
+                // tags usually separate words (e.g. `<li>`), so separate them
+                text.push(' ');
+            }
+            '>' if in_tag => in_tag = false,
+            _ if !in_tag => text.push(c),
+            _ => {}
+        }
+    }
+
+    let text = text
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&quot;", "\"")
+        .replace("&#39;", "'")
+        .replace("&#x27;", "'")
+        .replace("&amp;", "&");
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HTML: &str = "<h1>Databases</h1><p>Cot comes with an &lt;ORM&gt;.</p>\
+        <h2 id=\"models\"><a class=\"anchor-link\" href=\"#models\"></a>Models</h2>\
+        <p>Models are defined with the <code>#[model]</code> attribute.</p>\
+        <h3 id=\"migrations\"><a class=\"anchor-link\" href=\"#migrations\"></a>Migrations</h3>\
+        <p>Run <code>cot migration make</code> to generate migrations.</p>";
+
+    fn document() -> SearchDocument {
+        SearchDocument::new(
+            DocumentTarget::Guide("databases/overview".to_string()),
+            "Databases",
+            HTML,
+        )
+    }
+
+    #[test]
+    fn test_split_sections() {
+        let sections = split_sections(HTML);
+
+        assert_eq!(sections.len(), 3);
+        assert_eq!(sections[0].anchor, None);
+        assert_eq!(sections[0].text, "Databases Cot comes with an <ORM>.");
+        assert_eq!(sections[1].title.as_deref(), Some("Models"));
+        assert_eq!(sections[1].anchor.as_deref(), Some("models"));
+        assert_eq!(
+            sections[1].text,
+            "Models are defined with the #[model] attribute."
+        );
+        assert_eq!(sections[2].anchor.as_deref(), Some("migrations"));
+    }
+
+    #[test]
+    fn test_score_best_section() {
+        let document = document();
+
+        let (_, section) = document.score(&tokenize("migration")).unwrap();
+        assert_eq!(section.anchor.as_deref(), Some("migrations"));
+
+        let (_, section) = document.score(&tokenize("ORM")).unwrap();
+        assert_eq!(section.anchor, None);
+    }
+
+    #[test]
+    fn test_score_requires_all_terms() {
+        let document = document();
+
+        assert!(document.score(&tokenize("models migrations")).is_some());
+        assert!(document.score(&tokenize("models templates")).is_none());
+    }
+
+    #[test]
+    fn test_query_terms_limits() {
+        assert_eq!(query_terms("Models & migrations"), ["models", "migrations"]);
+
+        let many_terms = (0..MAX_QUERY_TERMS * 2)
+            .map(|i| format!("term{i}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        assert_eq!(query_terms(&many_terms).len(), MAX_QUERY_TERMS);
+
+        let long_term = "ż".repeat(MAX_QUERY_LENGTH * 2);
+        assert_eq!(query_terms(&long_term), ["ż".repeat(MAX_QUERY_LENGTH)]);
+    }
+
+    #[test]
+    fn test_excerpt() {
+        let terms = tokenize("orm");
+
+        assert_eq!(
+            excerpt("Cot comes with an <ORM>.", &terms),
+            "Cot comes with an <mark>&lt;ORM&gt;.</mark>"
+        );
+
+        let long_text = (0..100)
+            .map(|i| format!("word{i}"))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let excerpt = excerpt(&long_text, &tokenize("word50"));
+        assert!(excerpt.starts_with("… word43 "));
+        assert!(excerpt.contains("<mark>word50</mark>"));
+        assert!(excerpt.ends_with(" …"));
+    }
+}
@@ -0,0 +1,78 @@
+function activateTab(tabs, label) {
+  const buttons = tabs.querySelectorAll('[role="tab"]')
+  const panels = tabs.querySelectorAll('[role="tabpanel"]')
+
+  if (![...buttons].some((button) => button.dataset.codeTab === label)) {
+    return
+  }
+
+  for (const button of buttons) {
+    const selected = button.dataset.codeTab === label
+    button.classList.toggle('active', selected)
+    button.setAttribute('aria-selected', selected ? 'true' : 'false')
+    button.setAttribute('tabindex', selected ? '0' : '-1')
+  }
+
+  for (const panel of panels) {
+    panel.hidden = panel.dataset.codeTab !== label
+  }
+}
+
+function selectTab(button) {
+  const tabs = button.closest('.code-tabs')
+  const group = tabs.dataset.codeTabGroup
+  const label = button.dataset.codeTab
+
+  // keep all the tab widgets with the same group in sync
+  for (const otherTabs of document.querySelectorAll('.code-tabs')) {
+    if (otherTabs.dataset.codeTabGroup === group) {
+      activateTab(otherTabs, label)
+    }
+  }
+}
+
+document.addEventListener('click', (event) => {
+  const button = event.target.closest('.code-tabs [role="tab"]')
+
+  if (!button) {
+    return
+  }
+
+  // make sure the widget that was clicked stays in place when the widgets
+  // above it change their height
+  const offset = button.getBoundingClientRect().top
+  selectTab(button)
+  window.scrollBy(0, button.getBoundingClientRect().top - offset)
+})
+
+document.addEventListener('keydown', (event) => {
+  const button = event.target.closest('.code-tabs [role="tab"]')
+
+  if (!button) {
+    return
+  }
+
+  const buttons = [...button.parentElement.querySelectorAll('[role="tab"]')]
+  const index = buttons.indexOf(button)
+  let newIndex
+  switch (event.key) {
+    case 'ArrowLeft':
+      newIndex = (index - 1 + buttons.length) % buttons.length
+      break
+    case 'ArrowRight':
+      newIndex = (index + 1) % buttons.length
+      break
+    case 'Home':
+      newIndex = 0
+      break
+    case 'End':
+      newIndex = buttons.length - 1
+      break
+    default:
+      return
+  }
+
+  event.preventDefault()
+  buttons[newIndex].focus()
+  selectTab(buttons[newIndex])
+})
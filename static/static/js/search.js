@@ -1,9 +1,18 @@
-export async function initSearch(pagefind, currentVersion) {
+const MAX_RESULTS = 10;
+// when searching all versions, every page is shown once and the pages are
+// grouped by version, so more of them fit
+const MAX_ALL_VERSIONS_RESULTS = 20;
+
+export async function initSearch(pagefind, currentVersion, releasedVersions, unreleasedVersions) {
+    const latestVersion = releasedVersions[0];
+
     const searchInput = document.getElementById('cot-search');
     const searchResults = document.getElementById('search-results');
     const searchResultsList = document.getElementById('search-results-list');
     const searchQuerySpan = document.getElementById('search-query');
     const searchClose = document.getElementById('search-close');
+    const searchType = document.getElementById('search-type');
+    const searchVersions = document.getElementById('search-versions');
     const mainContent = document.querySelector('article');
     const cotToc = document.getElementsByClassName('cot-toc');
 
@@ -16,6 +25,18 @@ export async function initSearch(pagefind, currentVersion) {
         }
     });
 
+    for (const select of [searchType, searchVersions]) {
+        select.addEventListener('change', async () => {
+            const query = searchInput.value.trim();
+            if (query.length > 0) {
+                await performSearch(query);
+            }
+        });
+    }
+
+    // the form is only submitted (to the search page) when JavaScript is disabled
+    searchInput.form.addEventListener('submit', event => event.preventDefault());
+
     searchClose.addEventListener('click', () => {
         searchInput.value = '';
         resetSearch();
@@ -29,35 +50,148 @@ export async function initSearch(pagefind, currentVersion) {
         searchResults.classList.remove('d-none');
         searchQuerySpan.textContent = query;
 
+        const allVersionsMode = searchVersions.value === 'all';
         const DEBOUNCE_DELAY = 50; // ms
         const search = await pagefind.debouncedSearch(query, {
-            filters: {version: currentVersion}
+            filters: searchFilters(searchType.value, allVersionsMode)
         }, DEBOUNCE_DELAY);
         if (search === null) {
             // a more recent search call has been made, nothing to do
             return;
         }
 
+        // when searching all versions, the same page is usually found in many of
+        // them, so all the results are loaded to be deduplicated before they are
+        // truncated
+        const loadedResults = allVersionsMode ? search.results : search.results.slice(0, MAX_RESULTS);
+        const results = await Promise.all(loadedResults.map(result => result.data()));
+
         searchResultsList.innerHTML = '';
-        if (search.results.length === 0) {
+        if (results.length === 0) {
             searchResultsList.innerHTML = '<p>No results found.</p>';
+        } else if (allVersionsMode) {
+            renderVersionGroups(results);
         } else {
-            for (const result of search.results.slice(0, 10)) {
-                const data = await result.data();
-                const resultItem = document.createElement('div');
-                resultItem.classList.add('mb-4');
-
-                resultItem.innerHTML = `
-                    <h3><a href="${data.url}">${data.meta.title}</a></h3>
-                    ${data.sub_results.map(data => `
-                       <div class="search-result-sub-item">
-                         <a href="${data.url}">${data.title}</a>
-                         <p class="hit-content">${data.excerpt}</p>
-                       </div>
-                    `).join('')}
-                `;
-                searchResultsList.appendChild(resultItem);
+            for (const data of results) {
+                searchResultsList.appendChild(renderResult(data));
+            }
+        }
+    }
+
+    function renderResult(data, otherVersions = []) {
+        const resultItem = document.createElement('div');
+        resultItem.classList.add('mb-4');
+
+        resultItem.innerHTML = `
+            <h3><a href="${data.url}">${data.meta.title}</a></h3>
+            ${otherVersions.length > 0 ? `
+                <p class="small text-body-secondary">Also found in: ${otherVersions.join(', ')}</p>
+            ` : ''}
+            ${data.sub_results.map(data => `
+               <div class="search-result-sub-item">
+                 <a href="${data.url}">${data.title}</a>
+                 <p class="hit-content">${data.excerpt}</p>
+               </div>
+            `).join('')}
+        `;
+        return resultItem;
+    }
+
+    /**
+     * Renders the results of all the versions, grouped by version: the site
+     * pages first, then the released versions from the latest to the oldest,
+     * and finally the unreleased ones. Every page is only shown once, in the
+     * first of these versions it was found in, so the groups of the old
+     * versions only contain the pages that match the query in no newer
+     * released version (e.g. the ones about a removed API).
+     */
+    function renderVersionGroups(results) {
+        // the site pages don't have a version, so they are shown first
+        const versionOf = data => data.meta.version ?? null;
+        const versionRank = version => {
+            if (version === null) {
+                return -1;
+            }
+            const releasedRank = releasedVersions.indexOf(version);
+            if (releasedRank !== -1) {
+                return releasedRank;
+            }
+            return releasedVersions.length + unreleasedVersions.indexOf(version);
+        };
+        const compareVersions = (a, b) => versionRank(a) - versionRank(b);
+
+        const pages = new Map();
+        for (const data of results) {
+            const key = data.meta.link ?? data.url;
+            const page = pages.get(key);
+            if (page === undefined) {
+                pages.set(key, {data, otherVersions: []});
+            } else if (compareVersions(versionOf(data), versionOf(page.data)) < 0) {
+                page.otherVersions.push(versionOf(page.data));
+                page.data = data;
+            } else {
+                page.otherVersions.push(versionOf(data));
+            }
+        }
+
+        const groups = new Map();
+        for (const page of [...pages.values()].slice(0, MAX_ALL_VERSIONS_RESULTS)) {
+            const version = versionOf(page.data);
+            if (!groups.has(version)) {
+                groups.set(version, []);
+            }
+            groups.get(version).push(page);
+        }
+
+        for (const version of [...groups.keys()].sort(compareVersions)) {
+            const header = document.createElement('h2');
+            header.classList.add('h4', 'mt-4', 'mb-3', 'border-bottom', 'pb-2');
+            header.textContent = version ?? 'Site';
+            searchResultsList.appendChild(header);
+
+            const unreleased = unreleasedVersions.includes(version);
+            if (unreleased) {
+                const badge = document.createElement('span');
+                badge.classList.add('badge', 'text-bg-warning', 'ms-2', 'align-middle');
+                badge.textContent = 'Unreleased';
+                header.appendChild(badge);
             }
+
+            let noticeText = null;
+            if (unreleased) {
+                noticeText = 'These pages only match in versions that have not been released yet.';
+            } else if (version !== null && version !== latestVersion) {
+                noticeText = `These pages only match in ${version} and older versions, ` +
+                    `not in ${latestVersion}, the latest one.`;
+            }
+            if (noticeText !== null) {
+                const notice = document.createElement('div');
+                notice.classList.add('alert', 'alert-warning');
+                notice.setAttribute('role', 'alert');
+                notice.textContent = noticeText;
+                searchResultsList.appendChild(notice);
+            }
+
+            for (const page of groups.get(version)) {
+                page.otherVersions.sort(compareVersions);
+                searchResultsList.appendChild(renderResult(page.data, page.otherVersions));
+            }
+        }
+    }
+
+    function searchFilters(type, allVersionsMode) {
+        // the site pages don't belong to any version of the guide
+        const guideFilters = allVersionsMode
+            ? {type: 'Guide'}
+            : {type: 'Guide', version: currentVersion};
+        const siteFilters = {type: 'Site'};
+        switch (type) {
+            case 'Guide':
+                return guideFilters;
+            case 'Site':
+                return siteFilters;
+            default:
+                return {any: [guideFilters, siteFilters]};
         }
     }
 